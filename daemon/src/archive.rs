@@ -0,0 +1,856 @@
+//! SQLite store for archived teams at `~/.nightshift/archives.db`.
+//!
+//! Team summaries and usage are small and loaded at startup; member diffs and tool
+//! histories are read on demand. Archives written by older daemons as one JSON file
+//! per team in `~/.nightshift/team-archives/` are imported on open.
+//!
+//! The store also holds the task event log for active teams, which moves into the
+//! archive's task list when the team is archived.
+
+use crate::teams::{ArchivedTask, InboxMessage, TaskTransition, TeamSummary};
+use crate::toolcalls::{TokenUsage, ToolCall};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Applied in order; `PRAGMA user_version` records how many have run.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE teams (
+        name TEXT PRIMARY KEY,
+        archived_at INTEGER NOT NULL,
+        summary TEXT NOT NULL,
+        member_usage TEXT NOT NULL,
+        bytes INTEGER NOT NULL
+    );
+    CREATE TABLE members (
+        team TEXT NOT NULL,
+        name TEXT NOT NULL,
+        diff TEXT,
+        tools TEXT,
+        PRIMARY KEY (team, name)
+    );",
+    // v2: key archives by id so re-running a team doesn't overwrite its last archive.
+    "CREATE TABLE archives (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        archived_at INTEGER NOT NULL,
+        summary TEXT NOT NULL,
+        member_usage TEXT NOT NULL,
+        bytes INTEGER NOT NULL
+    );
+    CREATE INDEX archives_by_name ON archives (name, archived_at);
+    CREATE TABLE archive_members (
+        archive_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        diff TEXT,
+        tools TEXT,
+        PRIMARY KEY (archive_id, name)
+    );
+    INSERT INTO archives
+        SELECT name || '@' || archived_at, name, archived_at, summary, member_usage, bytes
+        FROM teams;
+    INSERT INTO archive_members
+        SELECT t.name || '@' || t.archived_at, m.name, m.diff, m.tools
+        FROM members m JOIN teams t ON t.name = m.team;
+    DROP TABLE members;
+    DROP TABLE teams;",
+    // v3: task files with their observed history, and member inboxes.
+    "ALTER TABLE archives ADD COLUMN tasks TEXT;
+    ALTER TABLE archive_members ADD COLUMN inbox TEXT;",
+    // v4: task status/owner transitions of active teams. A team run is identified
+    // by name plus `createdAt`.
+    "CREATE TABLE task_events (
+        team TEXT NOT NULL,
+        team_created_at INTEGER NOT NULL,
+        task_id TEXT NOT NULL,
+        status TEXT NOT NULL,
+        owner TEXT,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX task_events_by_team ON task_events (team, team_created_at, at);",
+];
+
+const MIGRATED_SUFFIX: &str = "migrated";
+
+/// A complete archive as produced when a team is deleted. Also the on-disk format of
+/// v1 JSON archives.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamArchive {
+    pub name: String,
+    pub archived_at: u64,
+    pub final_state: TeamSummary,
+    pub member_diffs: HashMap<String, String>,
+    pub member_tools: HashMap<String, Vec<ToolCall>>,
+    #[serde(default)]
+    pub member_usage: HashMap<String, TokenUsage>,
+    #[serde(default)]
+    pub tasks: Vec<ArchivedTask>,
+    #[serde(default)]
+    pub inboxes: HashMap<String, Vec<InboxMessage>>,
+}
+
+impl TeamArchive {
+    pub fn id(&self) -> String {
+        archive_id(&self.name, self.archived_at)
+    }
+}
+
+/// Archives are addressed as `<name>@<archived_at>`, so several runs of a team with
+/// the same name coexist. Must match the v2 migration.
+pub fn archive_id(name: &str, archived_at: u64) -> String {
+    format!("{name}@{archived_at}")
+}
+
+/// The eagerly loaded part of an archive.
+#[derive(Debug, Clone)]
+pub struct ArchivedTeam {
+    pub final_state: TeamSummary,
+    pub member_usage: HashMap<String, TokenUsage>,
+}
+
+/// Limits applied oldest-first after every archive. Unset fields don't limit.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<u64>,
+    pub max_count: Option<usize>,
+    pub max_bytes: Option<u64>,
+}
+
+pub struct ArchiveStore {
+    conn: Mutex<Connection>,
+}
+
+impl ArchiveStore {
+    pub fn open(path: &Path) -> Option<Self> {
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                tracing::warn!("failed to create archive store dir: {e}");
+                return None;
+            }
+        }
+        match Connection::open(path) {
+            Ok(conn) => Self::init(conn),
+            Err(e) => {
+                tracing::warn!("failed to open archive store {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Fallback when the on-disk store can't be opened: archives last until restart.
+    pub fn open_in_memory() -> Option<Self> {
+        Self::init(Connection::open_in_memory().ok()?)
+    }
+
+    fn init(conn: Connection) -> Option<Self> {
+        if let Err(e) = migrate(&conn) {
+            tracing::warn!("failed to migrate archive store: {e}");
+            return None;
+        }
+        Some(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store an archive, replacing any previous one with the same id.
+    /// Returns whether the archive was written.
+    pub fn save(&self, archive: &TeamArchive) -> bool {
+        let mut conn = self.conn();
+        match save_in(&mut conn, archive) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("failed to save archive '{}': {e}", archive.name);
+                false
+            }
+        }
+    }
+
+    /// Keyed by archive id.
+    pub fn load_all(&self) -> HashMap<String, ArchivedTeam> {
+        let conn = self.conn();
+        let result = (|| -> rusqlite::Result<HashMap<String, ArchivedTeam>> {
+            let mut stmt =
+                conn.prepare("SELECT id, archived_at, summary, member_usage FROM archives")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?;
+            let mut teams = HashMap::new();
+            for (id, archived_at, summary, usage) in rows.flatten() {
+                let Ok(mut final_state) = serde_json::from_str::<TeamSummary>(&summary) else {
+                    tracing::warn!("skipping archive '{id}' with unreadable summary");
+                    continue;
+                };
+                // Summaries from v1 JSON archives predate `archivedAt`
+                // and `archiveId`.
+                final_state.archived_at = Some(archived_at as u64);
+                final_state.archive_id = Some(id.clone());
+                teams.insert(
+                    id,
+                    ArchivedTeam {
+                        final_state,
+                        member_usage: serde_json::from_str(&usage).unwrap_or_default(),
+                    },
+                );
+            }
+            Ok(teams)
+        })();
+        result.unwrap_or_else(|e| {
+            tracing::warn!("failed to load archives: {e}");
+            HashMap::new()
+        })
+    }
+
+    pub fn delete(&self, id: &str) {
+        let conn = self.conn();
+        if let Err(e) = delete_in(&conn, id) {
+            tracing::warn!("failed to delete archive '{id}': {e}");
+        }
+    }
+
+    pub fn member_diff(&self, id: &str, member: &str) -> Option<String> {
+        self.member_column(id, member, "diff")
+    }
+
+    pub fn member_tools(&self, id: &str, member: &str) -> Option<Vec<ToolCall>> {
+        let json = self.member_column(id, member, "tools")?;
+        serde_json::from_str(&json).ok()
+    }
+
+    pub fn tasks(&self, id: &str) -> Vec<ArchivedTask> {
+        let conn = self.conn();
+        conn.query_row(
+            "SELECT tasks FROM archives WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+    }
+
+    pub fn team_inboxes(&self, id: &str) -> HashMap<String, Vec<InboxMessage>> {
+        let conn = self.conn();
+        let result = (|| -> rusqlite::Result<HashMap<String, Vec<InboxMessage>>> {
+            let mut stmt = conn.prepare(
+                "SELECT name, inbox FROM archive_members WHERE archive_id = ?1 AND inbox IS NOT NULL",
+            )?;
+            let rows = stmt.query_map(params![id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            Ok(rows
+                .flatten()
+                .filter_map(|(name, inbox)| Some((name, serde_json::from_str(&inbox).ok()?)))
+                .collect())
+        })();
+        result.unwrap_or_default()
+    }
+
+    pub fn append_task_events(
+        &self,
+        team: &str,
+        team_created_at: u64,
+        events: &[(String, TaskTransition)],
+    ) {
+        if events.is_empty() {
+            return;
+        }
+        let mut conn = self.conn();
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO task_events (team, team_created_at, task_id, status, owner, at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for (task_id, t) in events {
+                    stmt.execute(params![
+                        team,
+                        team_created_at as i64,
+                        task_id,
+                        t.status,
+                        t.owner,
+                        t.at as i64
+                    ])?;
+                }
+            }
+            tx.commit()
+        })();
+        if let Err(e) = result {
+            tracing::warn!("failed to log task events for '{team}': {e}");
+        }
+    }
+
+    /// Logged transitions per task id, oldest first.
+    pub fn task_events(
+        &self,
+        team: &str,
+        team_created_at: u64,
+    ) -> HashMap<String, Vec<TaskTransition>> {
+        let conn = self.conn();
+        let result = (|| -> rusqlite::Result<HashMap<String, Vec<TaskTransition>>> {
+            let mut stmt = conn.prepare(
+                "SELECT task_id, status, owner, at FROM task_events
+                 WHERE team = ?1 AND team_created_at = ?2
+                 ORDER BY at, rowid",
+            )?;
+            let rows = stmt.query_map(params![team, team_created_at as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    TaskTransition {
+                        status: row.get(1)?,
+                        owner: row.get(2)?,
+                        at: row.get::<_, i64>(3)? as u64,
+                    },
+                ))
+            })?;
+            let mut events: HashMap<String, Vec<TaskTransition>> = HashMap::new();
+            for (task_id, transition) in rows.flatten() {
+                events.entry(task_id).or_default().push(transition);
+            }
+            Ok(events)
+        })();
+        result.unwrap_or_else(|e| {
+            tracing::warn!("failed to read task events for '{team}': {e}");
+            HashMap::new()
+        })
+    }
+
+    pub fn clear_task_events(&self, team: &str, team_created_at: u64) {
+        let conn = self.conn();
+        if let Err(e) = conn.execute(
+            "DELETE FROM task_events WHERE team = ?1 AND team_created_at = ?2",
+            params![team, team_created_at as i64],
+        ) {
+            tracing::warn!("failed to clear task events for '{team}': {e}");
+        }
+    }
+
+    /// Every member's tool history for one archive, for reindexing.
+    pub fn team_tools(&self, id: &str) -> HashMap<String, Vec<ToolCall>> {
+        let conn = self.conn();
+        let result = (|| -> rusqlite::Result<HashMap<String, Vec<ToolCall>>> {
+            let mut stmt = conn.prepare(
+                "SELECT name, tools FROM archive_members WHERE archive_id = ?1 AND tools IS NOT NULL",
+            )?;
+            let rows = stmt.query_map(params![id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            Ok(rows
+                .flatten()
+                .filter_map(|(name, tools)| Some((name, serde_json::from_str(&tools).ok()?)))
+                .collect())
+        })();
+        result.unwrap_or_default()
+    }
+
+    fn member_column(&self, id: &str, member: &str, column: &str) -> Option<String> {
+        let conn = self.conn();
+        conn.query_row(
+            &format!("SELECT {column} FROM archive_members WHERE archive_id = ?1 AND name = ?2"),
+            params![id, member],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .flatten()
+    }
+
+    /// Drop archives outside `policy`, oldest first. Returns the removed archive ids.
+    pub fn prune(&self, policy: &RetentionPolicy, now_ms: u64) -> Vec<String> {
+        let mut conn = self.conn();
+        match prune_in(&mut conn, policy, now_ms) {
+            Ok(removed) => removed,
+            Err(e) => {
+                tracing::warn!("failed to apply archive retention: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Import v1 JSON archives from `dir`, renaming each imported file to
+    /// `<name>.json.migrated` so it is not imported twice.
+    pub fn import_json_dir(&self, dir: &Path) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut imported = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e != "json").unwrap_or(true) {
+                continue;
+            }
+            let archive = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<TeamArchive>(&s).ok());
+            let Some(archive) = archive else {
+                tracing::warn!("skipping unreadable archive {}", path.display());
+                continue;
+            };
+            {
+                let mut conn = self.conn();
+                if let Err(e) = save_in(&mut conn, &archive) {
+                    tracing::warn!("failed to import archive {}: {e}", path.display());
+                    continue;
+                }
+            }
+            let mut migrated = path.clone().into_os_string();
+            migrated.push(format!(".{MIGRATED_SUFFIX}"));
+            if let Err(e) = std::fs::rename(&path, &migrated) {
+                tracing::warn!("failed to mark {} as migrated: {e}", path.display());
+            }
+            imported += 1;
+        }
+        imported
+    }
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: usize =
+        conn.query_row("PRAGMA user_version", [], |r| r.get::<_, i64>(0))? as usize;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN;\n{sql}\nPRAGMA user_version = {};\nCOMMIT;",
+            i + 1
+        ))?;
+    }
+    Ok(())
+}
+
+/// Per-member columns of `archive_members`: diff, tools JSON, inbox JSON.
+#[derive(Default)]
+struct MemberRow<'a> {
+    diff: Option<&'a str>,
+    tools: Option<String>,
+    inbox: Option<String>,
+}
+
+impl MemberRow<'_> {
+    fn len(&self) -> usize {
+        self.diff.map_or(0, str::len)
+            + self.tools.as_ref().map_or(0, String::len)
+            + self.inbox.as_ref().map_or(0, String::len)
+    }
+}
+
+fn save_in(conn: &mut Connection, archive: &TeamArchive) -> rusqlite::Result<()> {
+    let summary = to_json(&archive.final_state);
+    let usage = to_json(&archive.member_usage);
+    let tasks = to_json(&archive.tasks);
+
+    let mut members: HashMap<&str, MemberRow> = HashMap::new();
+    for (name, diff) in &archive.member_diffs {
+        members.entry(name).or_default().diff = Some(diff);
+    }
+    for (name, calls) in &archive.member_tools {
+        members.entry(name).or_default().tools = Some(to_json(calls));
+    }
+    for (name, messages) in &archive.inboxes {
+        members.entry(name).or_default().inbox = Some(to_json(messages));
+    }
+
+    let bytes = summary.len()
+        + usage.len()
+        + tasks.len()
+        + members.values().map(MemberRow::len).sum::<usize>();
+
+    let id = archive.id();
+    let tx = conn.transaction()?;
+    delete_in(&tx, &id)?;
+    tx.execute(
+        "INSERT INTO archives (id, name, archived_at, summary, member_usage, bytes, tasks)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id,
+            archive.name,
+            archive.archived_at as i64,
+            summary,
+            usage,
+            bytes as i64,
+            tasks
+        ],
+    )?;
+    for (member, row) in &members {
+        tx.execute(
+            "INSERT INTO archive_members (archive_id, name, diff, tools, inbox)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, member, row.diff, row.tools, row.inbox],
+        )?;
+    }
+    tx.commit()
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn delete_in(conn: &Connection, id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM archive_members WHERE archive_id = ?1",
+        params![id],
+    )?;
+    conn.execute("DELETE FROM archives WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+fn prune_in(
+    conn: &mut Connection,
+    policy: &RetentionPolicy,
+    now_ms: u64,
+) -> rusqlite::Result<Vec<String>> {
+    let mut teams: Vec<(String, u64, u64)> = {
+        let mut stmt =
+            conn.prepare("SELECT id, archived_at, bytes FROM archives ORDER BY archived_at DESC")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, i64>(2)? as u64,
+            ))
+        })?;
+        rows.flatten().collect()
+    };
+
+    // Walk newest to oldest keeping what fits; everything after the
+    // first archive that breaks a limit goes, so retention never leaves gaps.
+    let min_archived_at = policy
+        .max_age_days
+        .map(|days| now_ms.saturating_sub(days * 24 * 60 * 60 * 1000));
+    let mut total_bytes = 0u64;
+    let keep = teams
+        .iter()
+        .enumerate()
+        .position(|(i, (_, archived_at, bytes))| {
+            total_bytes += bytes;
+            min_archived_at.is_some_and(|min| *archived_at < min)
+                || policy.max_count.is_some_and(|max| i >= max)
+                || policy.max_bytes.is_some_and(|max| total_bytes > max)
+        })
+        .unwrap_or(teams.len());
+    let removed: Vec<String> = teams.drain(keep..).map(|(id, _, _)| id).collect();
+
+    if !removed.is_empty() {
+        let tx = conn.transaction()?;
+        for name in &removed {
+            delete_in(&tx, name)?;
+        }
+        tx.commit()?;
+    }
+    Ok(removed)
+}
+
+/// Gzipped tarball of `entries` (relative path, contents), all under `root/`.
+pub fn tar_gz(root: &str, entries: &[(String, Vec<u8>)]) -> std::io::Result<Vec<u8>> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    for (path, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, format!("{root}/{path}"), contents.as_slice())?;
+    }
+    builder.into_inner()?.finish()
+}
+
+/// Make a team or member name safe to use as a single path component.
+pub fn path_component(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    match cleaned.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => cleaned,
+    }
+}
+
+pub fn store_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".nightshift/archives.db")
+}
+
+pub fn legacy_archive_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".nightshift/team-archives")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn archive(name: &str, archived_at: u64, diff: &str) -> TeamArchive {
+        TeamArchive {
+            name: name.to_string(),
+            archived_at,
+            final_state: TeamSummary {
+                name: name.to_string(),
+                description: String::new(),
+                created_at: 0,
+                archived: true,
+                archived_at: Some(archived_at),
+                archive_id: None,
+                members: Vec::new(),
+                tasks: Vec::new(),
+                conflicts: Vec::new(),
+            },
+            member_diffs: HashMap::from([("w1".to_string(), diff.to_string())]),
+            member_tools: HashMap::from([(
+                "w1".to_string(),
+                vec![ToolCall {
+                    id: "t1".into(),
+                    tool: "Bash".into(),
+                    title: None,
+                    input_summary: "cargo test".into(),
+                    status: "completed".into(),
+                    timestamp: archived_at,
+                    duration_ms: Some(5),
+                    input_text: "cargo test".into(),
+                }],
+            )]),
+            member_usage: HashMap::from([(
+                "w1".to_string(),
+                TokenUsage {
+                    input_tokens: 10,
+                    ..Default::default()
+                },
+            )]),
+            tasks: vec![ArchivedTask {
+                id: "1".into(),
+                subject: "Fix the bug".into(),
+                description: String::new(),
+                status: "completed".into(),
+                owner: Some("w1".into()),
+                blocks: Vec::new(),
+                blocked_by: Vec::new(),
+                history: Vec::new(),
+            }],
+            inboxes: HashMap::from([(
+                "w1".to_string(),
+                vec![InboxMessage {
+                    from: "team-lead".into(),
+                    text: "start on #1".into(),
+                    summary: None,
+                    timestamp: None,
+                    read: true,
+                }],
+            )]),
+        }
+    }
+
+    #[test]
+    fn should_load_summaries_eagerly_and_details_on_demand() {
+        let store = ArchiveStore::open_in_memory().unwrap();
+        store.save(&archive("alpha", 1, "diff --git a/x b/x"));
+
+        let all = store.load_all();
+        let team = &all["alpha@1"];
+        assert_eq!(team.final_state.name, "alpha");
+        assert_eq!(team.final_state.archive_id.as_deref(), Some("alpha@1"));
+        assert_eq!(team.member_usage["w1"].input_tokens, 10);
+        assert_eq!(
+            store.member_diff("alpha@1", "w1").as_deref(),
+            Some("diff --git a/x b/x")
+        );
+        assert_eq!(store.member_tools("alpha@1", "w1").unwrap()[0].id, "t1");
+        assert!(store.member_diff("alpha@1", "w2").is_none());
+        assert_eq!(store.team_tools("alpha@1").len(), 1);
+        assert_eq!(store.tasks("alpha@1")[0].subject, "Fix the bug");
+        assert_eq!(store.team_inboxes("alpha@1")["w1"][0].text, "start on #1");
+    }
+
+    #[test]
+    fn should_delete_archive_and_its_members() {
+        let store = ArchiveStore::open_in_memory().unwrap();
+        store.save(&archive("alpha", 1, "d"));
+        store.delete("alpha@1");
+        assert!(store.load_all().is_empty());
+        assert!(store.member_diff("alpha@1", "w1").is_none());
+    }
+
+    #[test]
+    fn should_bundle_entries_under_root() {
+        let entries = vec![
+            ("summary.json".to_string(), b"{}".to_vec()),
+            ("members/w1/changes.patch".to_string(), b"diff".to_vec()),
+        ];
+        let bytes = tar_gz("alpha", &entries).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(bytes.as_slice()));
+        let paths: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["alpha/summary.json", "alpha/members/w1/changes.patch"]
+        );
+    }
+
+    #[test]
+    fn should_sanitize_path_components() {
+        assert_eq!(path_component("frontend"), "frontend");
+        assert_eq!(path_component("../etc"), ".._etc");
+        assert_eq!(path_component(".."), "_");
+    }
+
+    #[test]
+    fn should_log_task_events_per_team_run() {
+        let store = ArchiveStore::open_in_memory().unwrap();
+        let transition = |status: &str, at: u64| TaskTransition {
+            status: status.to_string(),
+            owner: None,
+            at,
+        };
+        store.append_task_events(
+            "alpha",
+            1,
+            &[
+                ("1".into(), transition("pending", 10)),
+                ("1".into(), transition("completed", 20)),
+            ],
+        );
+        store.append_task_events("alpha", 2, &[("1".into(), transition("pending", 30))]);
+
+        let first_run = store.task_events("alpha", 1);
+        let statuses: Vec<&str> = first_run["1"].iter().map(|t| t.status.as_str()).collect();
+        assert_eq!(statuses, vec!["pending", "completed"]);
+        assert_eq!(store.task_events("alpha", 2)["1"].len(), 1);
+
+        store.clear_task_events("alpha", 1);
+        assert!(store.task_events("alpha", 1).is_empty());
+        assert_eq!(store.task_events("alpha", 2).len(), 1);
+    }
+
+    #[test]
+    fn should_keep_archives_with_same_name() {
+        let store = ArchiveStore::open_in_memory().unwrap();
+        store.save(&archive("alpha", 1, "first run"));
+        store.save(&archive("alpha", 2, "second run"));
+        assert_eq!(store.load_all().len(), 2);
+        assert_eq!(
+            store.member_diff("alpha@1", "w1").as_deref(),
+            Some("first run")
+        );
+        assert_eq!(
+            store.member_diff("alpha@2", "w1").as_deref(),
+            Some("second run")
+        );
+    }
+
+    #[test]
+    fn should_migrate_name_keyed_archives_to_ids() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!("{}\nPRAGMA user_version = 1;", MIGRATIONS[0]))
+            .unwrap();
+        let summary = to_json(&archive("alpha", 7, "d").final_state);
+        conn.execute(
+            "INSERT INTO teams VALUES ('alpha', 7, ?1, '{}', 10)",
+            params![summary],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO members VALUES ('alpha', 'w1', 'd', NULL)", [])
+            .unwrap();
+
+        let store = ArchiveStore::init(conn).unwrap();
+        assert!(store.load_all().contains_key("alpha@7"));
+        assert_eq!(store.member_diff("alpha@7", "w1").as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn should_import_json_archives_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = serde_json::to_string(&archive("alpha", 1, "d")).unwrap();
+        std::fs::write(dir.path().join("alpha.json"), json).unwrap();
+        std::fs::write(dir.path().join("broken.json"), "not json").unwrap();
+
+        let store = ArchiveStore::open_in_memory().unwrap();
+        assert_eq!(store.import_json_dir(dir.path()), 1);
+        assert_eq!(store.import_json_dir(dir.path()), 0);
+        assert!(dir.path().join("alpha.json.migrated").exists());
+        assert!(dir.path().join("broken.json").exists());
+        assert_eq!(store.member_diff("alpha@1", "w1").as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn should_not_rerun_migrations_on_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archives.db");
+        ArchiveStore::open(&path)
+            .unwrap()
+            .save(&archive("alpha", 1, "d"));
+
+        let store = ArchiveStore::open(&path).unwrap();
+        assert_eq!(store.load_all().len(), 1);
+        let version: i64 = store
+            .conn()
+            .query_row("PRAGMA user_version", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn should_prune_by_age_count_and_bytes() {
+        let now = 100 * DAY_MS;
+        let store = ArchiveStore::open_in_memory().unwrap();
+        store.save(&archive("old", now - 40 * DAY_MS, "d"));
+        store.save(&archive("mid", now - 2 * DAY_MS, "d"));
+        store.save(&archive("new", now - DAY_MS, "d"));
+
+        let by_age = RetentionPolicy {
+            max_age_days: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.prune(&by_age, now),
+            vec![archive_id("old", now - 40 * DAY_MS)]
+        );
+
+        let by_count = RetentionPolicy {
+            max_count: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.prune(&by_count, now),
+            vec![archive_id("mid", now - 2 * DAY_MS)]
+        );
+
+        let by_bytes = RetentionPolicy {
+            max_bytes: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            store.prune(&by_bytes, now),
+            vec![archive_id("new", now - DAY_MS)]
+        );
+        assert!(store.load_all().is_empty());
+    }
+}
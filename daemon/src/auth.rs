@@ -0,0 +1,431 @@
+//! Request authentication for the daemon proxy: static bearer tokens and optional
+//! HMAC-signed requests, configured under `auth` in `config.json`.
+//!
+//! A signed request carries `x-nightshift-timestamp` (unix seconds),
+//! `x-nightshift-content-sha256` (hex SHA-256 of the body, of nothing for bodiless requests)
+//! and `x-nightshift-signature`, the hex HMAC-SHA256 of
+//! `"{timestamp}\n{METHOD}\n{path?query}\n{content-sha256}"` under the shared secret.
+//! Signed bodies are buffered to check the digest, and each signature is accepted once.
+
+use axum::body::Body;
+use axum::extract::connect_info::Connected;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{Extensions, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::serve::IncomingStream;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+pub const TIMESTAMP_HEADER: &str = "x-nightshift-timestamp";
+pub const CONTENT_SHA256_HEADER: &str = "x-nightshift-content-sha256";
+pub const SIGNATURE_HEADER: &str = "x-nightshift-signature";
+
+/// Probe endpoints that load balancers and service managers hit without credentials.
+/// Handlers check for [`Authenticated`] before returning anything beyond up/down.
+pub const PUBLIC_PATHS: &[&str] = &["/health", "/ready"];
+
+/// Signed requests older or newer than this are rejected to limit replay.
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// A shorter HMAC secret is guessable offline from a single signed request.
+const MIN_HMAC_SECRET_BYTES: usize = 32;
+
+/// Signed bodies are held in memory until their digest checks out.
+const MAX_SIGNED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Used signatures past their skew window are forgotten once this many are held.
+const SEEN_PRUNE_AT: usize = 1024;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    pub hmac_secret: Option<String>,
+    /// Let requests from 127.0.0.1/::1 through without credentials.
+    #[serde(default)]
+    pub allow_loopback: bool,
+}
+
+impl AuthConfig {
+    /// Auth is off unless at least one credential is configured.
+    pub fn enabled(&self) -> bool {
+        !self.tokens.is_empty() || self.hmac_secret.is_some()
+    }
+
+    /// Rejects credentials that would silently weaken or disable auth.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.tokens.iter().any(|t| t.trim().is_empty()) {
+            anyhow::bail!("auth.tokens must not contain empty tokens");
+        }
+        if let Some(ref secret) = self.hmac_secret {
+            if secret.len() < MIN_HMAC_SECRET_BYTES {
+                anyhow::bail!("auth.hmacSecret must be at least {MIN_HMAC_SECRET_BYTES} bytes");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Request extension set once the caller has passed auth, or when auth is off.
+#[derive(Clone, Copy, Debug)]
+pub struct Authenticated;
+
+pub struct Auth {
+    enabled: bool,
+    tokens: Vec<String>,
+    hmac_key: Option<ring::hmac::Key>,
+    allow_loopback: bool,
+    /// Signatures already accepted, with the unix second they stop being valid.
+    seen: Mutex<HashMap<Vec<u8>, u64>>,
+}
+
+/// A request whose signature checked out, pending its body digest and replay check.
+struct SignedRequest {
+    tag: Vec<u8>,
+    content_sha256: Vec<u8>,
+    timestamp: u64,
+}
+
+impl Auth {
+    /// `config` must have passed [`AuthConfig::validate`].
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            enabled: config.enabled(),
+            tokens: config.tokens.clone(),
+            hmac_key: config
+                .hmac_secret
+                .as_ref()
+                .map(|s| ring::hmac::Key::new(ring::hmac::HMAC_SHA256, s.as_bytes())),
+            allow_loopback: config.allow_loopback,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn check_token(&self, headers: &HeaderMap) -> bool {
+        bearer_token(headers).is_some_and(|token| {
+            self.tokens
+                .iter()
+                .any(|t| constant_time_eq(t.as_bytes(), token.as_bytes()))
+        })
+    }
+
+    fn check_signature(
+        &self,
+        headers: &HeaderMap,
+        method: &str,
+        path: &str,
+        now_secs: u64,
+    ) -> Option<SignedRequest> {
+        verify_signature(self.hmac_key.as_ref()?, headers, method, path, now_secs)
+    }
+
+    /// Accepts a signed request whose body hashes to the signed digest, once.
+    fn accept_signed(&self, signed: &SignedRequest, body: &[u8], now_secs: u64) -> bool {
+        let digest = ring::digest::digest(&ring::digest::SHA256, body);
+        if !constant_time_eq(digest.as_ref(), &signed.content_sha256) {
+            return false;
+        }
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if seen.len() >= SEEN_PRUNE_AT {
+            seen.retain(|_, expires| *expires >= now_secs);
+        }
+        seen.insert(signed.tag.clone(), signed.timestamp + MAX_CLOCK_SKEW_SECS)
+            .is_none()
+    }
+}
+
+pub async fn require_auth(State(auth): State<Arc<Auth>>, mut req: Request, next: Next) -> Response {
+    let now = now_secs();
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+    let signed = auth.check_signature(req.headers(), req.method().as_str(), path, now);
+    let mut authenticated = !auth.enabled
+        || (auth.allow_loopback && is_local_peer(req.extensions()))
+        || auth.check_token(req.headers());
+
+    if let (false, Some(signed)) = (authenticated, signed) {
+        let (parts, body) = req.into_parts();
+        let Ok(body) = axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await else {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                [("content-type", "application/json")],
+                r#"{"error":"signed body too large"}"#,
+            )
+                .into_response();
+        };
+        authenticated = auth.accept_signed(&signed, &body, now);
+        req = Request::from_parts(parts, Body::from(body));
+    }
+
+    if authenticated {
+        req.extensions_mut().insert(Authenticated);
+        return next.run(req).await;
+    }
+    if PUBLIC_PATHS.contains(&req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    (
+        StatusCode::UNAUTHORIZED,
+        [
+            ("content-type", "application/json"),
+            ("www-authenticate", "Bearer"),
+        ],
+        r#"{"error":"unauthorized"}"#,
+    )
+        .into_response()
+}
+
+/// Connect info for connections accepted on the Unix socket, which are always local.
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug)]
+pub struct UnixPeer;
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for UnixPeer {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        UnixPeer
+    }
+}
+
+pub fn is_local_peer(extensions: &Extensions) -> bool {
+    #[cfg(unix)]
+    if extensions.get::<ConnectInfo<UnixPeer>>().is_some() {
+        return true;
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .is_some_and(|ConnectInfo(addr)| addr.ip().is_loopback())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn verify_signature(
+    key: &ring::hmac::Key,
+    headers: &HeaderMap,
+    method: &str,
+    path: &str,
+    now_secs: u64,
+) -> Option<SignedRequest> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let timestamp = header(TIMESTAMP_HEADER)?;
+    let content_sha256 = header(CONTENT_SHA256_HEADER)?;
+    let ts = timestamp.parse::<u64>().ok()?;
+    if ts.abs_diff(now_secs) > MAX_CLOCK_SKEW_SECS {
+        return None;
+    }
+    let tag = decode_hex(header(SIGNATURE_HEADER)?)?;
+    let payload = signing_payload(timestamp, method, path, content_sha256);
+    ring::hmac::verify(key, payload.as_bytes(), &tag).ok()?;
+    Some(SignedRequest {
+        tag,
+        content_sha256: decode_hex(content_sha256)?,
+        timestamp: ts,
+    })
+}
+
+pub fn signing_payload(timestamp: &str, method: &str, path: &str, content_sha256: &str) -> String {
+    format!(
+        "{timestamp}\n{}\n{path}\n{}",
+        method.to_ascii_uppercase(),
+        content_sha256.to_ascii_lowercase()
+    )
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn auth(tokens: &[&str], secret: Option<&str>) -> Auth {
+        Auth::new(&AuthConfig {
+            tokens: tokens.iter().map(|t| t.to_string()).collect(),
+            hmac_secret: secret.map(str::to_string),
+            allow_loopback: false,
+        })
+    }
+
+    fn headers(pairs: &[(&'static str, String)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.insert(*k, v.parse().unwrap());
+        }
+        map
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Headers for a request signed with `secret` over `body`.
+    fn signed(secret: &str, ts: u64, method: &str, path: &str, body: &[u8]) -> HeaderMap {
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+        let digest = hex(ring::digest::digest(&ring::digest::SHA256, body).as_ref());
+        let payload = signing_payload(&ts.to_string(), method, path, &digest);
+        headers(&[
+            (TIMESTAMP_HEADER, ts.to_string()),
+            (CONTENT_SHA256_HEADER, digest),
+            (
+                SIGNATURE_HEADER,
+                hex(ring::hmac::sign(&key, payload.as_bytes()).as_ref()),
+            ),
+        ])
+    }
+
+    #[test]
+    fn should_accept_configured_bearer_tokens_only() {
+        let auth = auth(&["secret-1", "secret-2"], None);
+        let ok = headers(&[("authorization", "Bearer secret-2".into())]);
+        let wrong = headers(&[("authorization", "Bearer secret-3".into())]);
+        let basic = headers(&[("authorization", "Basic secret-1".into())]);
+        assert!(auth.check_token(&ok));
+        assert!(!auth.check_token(&wrong));
+        assert!(!auth.check_token(&basic));
+        assert!(!auth.check_token(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn should_verify_hmac_signed_requests() {
+        let auth = auth(&[], Some(SECRET));
+        let check = |headers: &HeaderMap, method: &str, path: &str| {
+            auth.check_signature(headers, method, path, NOW).is_some()
+        };
+        let get = |ts: u64, path: &str| signed(SECRET, ts, "GET", path, b"");
+        assert!(check(
+            &signed(SECRET, NOW, "get", "/teams?archived=true", b""),
+            "GET",
+            "/teams?archived=true"
+        ));
+        assert!(!check(&get(NOW, "/teams"), "DELETE", "/teams"));
+        assert!(!check(&get(NOW, "/teams"), "GET", "/search"));
+        assert!(!check(&get(NOW - 301, "/teams"), "GET", "/teams"));
+        assert!(!check(
+            &signed("other", NOW, "GET", "/teams", b""),
+            "GET",
+            "/teams"
+        ));
+
+        let mut unsigned_digest = get(NOW, "/teams");
+        unsigned_digest.insert(CONTENT_SHA256_HEADER, hex(&[0; 32]).parse().unwrap());
+        assert!(!check(&unsigned_digest, "GET", "/teams"));
+    }
+
+    #[test]
+    fn should_reject_signed_requests_with_a_swapped_body() {
+        let auth = auth(&[], Some(SECRET));
+        let body = br#"{"command":"ls"}"#;
+        let headers = signed(SECRET, NOW, "POST", "/session/s1/shell", body);
+        let request = auth
+            .check_signature(&headers, "POST", "/session/s1/shell", NOW)
+            .unwrap();
+        assert!(!auth.accept_signed(&request, br#"{"command":"rm -rf ~"}"#, NOW));
+        assert!(auth.accept_signed(&request, body, NOW));
+    }
+
+    #[test]
+    fn should_reject_replayed_signatures() {
+        let auth = auth(&[], Some(SECRET));
+        let headers = signed(SECRET, NOW, "PATCH", "/config", b"{}");
+        let first = auth
+            .check_signature(&headers, "PATCH", "/config", NOW)
+            .unwrap();
+        assert!(auth.accept_signed(&first, b"{}", NOW));
+        let replay = auth
+            .check_signature(&headers, "PATCH", "/config", NOW + 10)
+            .unwrap();
+        assert!(!auth.accept_signed(&replay, b"{}", NOW + 10));
+
+        let fresh = signed(SECRET, NOW + 1, "PATCH", "/config", b"{}");
+        let fresh = auth
+            .check_signature(&fresh, "PATCH", "/config", NOW + 10)
+            .unwrap();
+        assert!(auth.accept_signed(&fresh, b"{}", NOW + 10));
+    }
+
+    #[test]
+    fn should_be_disabled_without_credentials() {
+        assert!(!AuthConfig::default().enabled());
+        assert!(!auth(&[], None).enabled);
+        assert!(auth(&["t"], None).enabled);
+        assert!(auth(&[], Some(SECRET)).enabled);
+    }
+
+    #[test]
+    fn should_reject_empty_tokens_and_short_secrets() {
+        let config = |tokens: &[&str], secret: Option<&str>| AuthConfig {
+            tokens: tokens.iter().map(|t| t.to_string()).collect(),
+            hmac_secret: secret.map(str::to_string),
+            allow_loopback: false,
+        };
+        assert!(config(&[], None).validate().is_ok());
+        assert!(config(&["t"], Some(SECRET)).validate().is_ok());
+        assert!(config(&["t", ""], None).validate().is_err());
+        assert!(config(&["  "], None).validate().is_err());
+        assert!(config(&[], Some("")).validate().is_err());
+        assert!(config(&[], Some("shh")).validate().is_err());
+    }
+
+    #[test]
+    fn should_treat_loopback_and_unix_peers_as_local() {
+        let peer = |addr: &str| {
+            let mut ext = Extensions::new();
+            ext.insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+            ext
+        };
+        assert!(is_local_peer(&peer("127.0.0.1:5000")));
+        assert!(is_local_peer(&peer("[::1]:5000")));
+        assert!(!is_local_peer(&peer("10.0.0.2:5000")));
+        assert!(!is_local_peer(&Extensions::new()));
+
+        #[cfg(unix)]
+        {
+            let mut ext = Extensions::new();
+            ext.insert(ConnectInfo(UnixPeer));
+            assert!(is_local_peer(&ext));
+        }
+    }
+
+    #[test]
+    fn should_decode_hex() {
+        assert_eq!(decode_hex("00ff10"), Some(vec![0x00, 0xff, 0x10]));
+        assert_eq!(decode_hex("0"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+}
@@ -0,0 +1,771 @@
+//! Claude Code backend: sessions are JSONL transcripts under `~/.claude/`.
+
+use super::{AgentBackend, MemberInfo, ResolvedSession, SessionRef};
+use crate::toolcalls::{
+    estimate_cost, input_text, parse_iso_timestamp, summarize_input, truncate_output, TokenUsage,
+    ToolCall, ToolCallDetail, TranscriptBlock, TranscriptMessage,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct ClaudeBackend;
+
+impl AgentBackend for ClaudeBackend {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn resolve_session(&self, member: &MemberInfo) -> Option<ResolvedSession> {
+        let claude_dir = crate::teams::claude_dir();
+        let (path, method) = resolve_via_tmux(&claude_dir, member)
+            .map(|p| (p, "tmux"))
+            .or_else(|| {
+                resolve_via_project_scan(&claude_dir, member).map(|p| (p, "project-scan"))
+            })?;
+        Some(ResolvedSession {
+            session: SessionRef::Path(path),
+            method,
+        })
+    }
+
+    fn session_superseded(&self, member: &MemberInfo, session: &SessionRef) -> bool {
+        let Some(path) = session.as_path() else {
+            return false;
+        };
+        let Some(modified) = std::fs::metadata(path).and_then(|m| m.modified()).ok() else {
+            return true;
+        };
+        // `/clear` starts a fresh transcript next to the old one. Other members
+        // share the project dir, so only a newer transcript of this member counts.
+        let needles = member_needles(member);
+        transcripts_in(path.parent().unwrap_or(path))
+            .iter()
+            .any(|(other, other_modified)| {
+                other != path && *other_modified > modified && mentions_member(other, &needles)
+            })
+    }
+
+    fn read_tools(&self, session: &SessionRef) -> Vec<ToolCall> {
+        session.as_path().map(read_claude_tools).unwrap_or_default()
+    }
+
+    fn read_tool_detail(&self, session: &SessionRef, call_id: &str) -> Option<ToolCallDetail> {
+        read_claude_tool_detail(session.as_path()?, call_id)
+    }
+
+    fn read_transcript(&self, session: &SessionRef) -> Vec<TranscriptMessage> {
+        session
+            .as_path()
+            .map(read_claude_transcript)
+            .unwrap_or_default()
+    }
+
+    fn read_usage(&self, session: &SessionRef) -> TokenUsage {
+        session.as_path().map(read_claude_usage).unwrap_or_default()
+    }
+
+    fn is_live(&self, session: &SessionRef) -> bool {
+        session
+            .as_path()
+            .map(super::file_recently_modified)
+            .unwrap_or(false)
+    }
+}
+
+// --- Session resolution (active-sessions.json) ---
+
+#[derive(Deserialize, Debug)]
+struct ActiveSessions {
+    sessions: HashMap<String, ActiveSession>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ActiveSession {
+    transcript_path: String,
+    #[serde(default)]
+    tmux: Option<TmuxInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TmuxInfo {
+    pane_id: String,
+}
+
+/// Bytes of each candidate transcript inspected when matching by project scan.
+const PROJECT_SCAN_HEAD_BYTES: u64 = 64 * 1024;
+
+fn resolve_via_tmux(claude_dir: &Path, member: &MemberInfo) -> Option<PathBuf> {
+    let pane_id = member.tmux_pane_id?;
+    let active_sessions_path = claude_dir.join("active-sessions.json");
+    let contents = std::fs::read_to_string(active_sessions_path).ok()?;
+    let sessions: ActiveSessions = serde_json::from_str(&contents).ok()?;
+
+    for session in sessions.sessions.values() {
+        if let Some(ref tmux) = session.tmux {
+            if tmux.pane_id == pane_id {
+                let path = PathBuf::from(&session.transcript_path);
+                if path.exists() {
+                    return Some(path);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Fallback for members without tmux: newest transcript in the member's project
+/// dir that mentions its agent id (or, lacking one, both team and member name).
+fn resolve_via_project_scan(claude_dir: &Path, member: &MemberInfo) -> Option<PathBuf> {
+    if member.cwd.is_empty() {
+        return None;
+    }
+    let project_dir = claude_dir
+        .join("projects")
+        .join(encode_project_dir(member.cwd));
+
+    let needles = member_needles(member);
+    let mut candidates = transcripts_in(&project_dir);
+    candidates.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    candidates
+        .into_iter()
+        .map(|(path, _)| path)
+        .find(|path| mentions_member(path, &needles))
+}
+
+/// Strings a member's transcript must contain: its agent id or, lacking one,
+/// both team and member name.
+fn member_needles(member: &MemberInfo) -> Vec<String> {
+    if member.agent_id.is_empty() {
+        vec![
+            format!("\"{}\"", member.team_name),
+            format!("\"{}\"", member.name),
+        ]
+    } else {
+        vec![format!("\"{}\"", member.agent_id)]
+    }
+}
+
+fn mentions_member(path: &Path, needles: &[String]) -> bool {
+    let head = super::read_head(path, PROJECT_SCAN_HEAD_BYTES);
+    needles.iter().all(|n| head.contains(n.as_str()))
+}
+
+/// Claude Code names project dirs after the cwd with every non-alphanumeric
+/// character replaced by `-`, e.g. `/Users/me/my.app` -> `-Users-me-my-app`.
+fn encode_project_dir(cwd: &str) -> String {
+    cwd.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn transcripts_in(dir: &Path) -> Vec<(PathBuf, std::time::SystemTime)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|e| e == "jsonl").unwrap_or(false))
+        .filter_map(|p| {
+            let modified = std::fs::metadata(&p).and_then(|m| m.modified()).ok()?;
+            Some((p, modified))
+        })
+        .collect()
+}
+
+// --- Transcript readers ---
+
+pub fn read_claude_tools(transcript_path: &Path) -> Vec<ToolCall> {
+    if !transcript_path.exists() {
+        return Vec::new();
+    }
+
+    let contents = match std::fs::read_to_string(transcript_path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(
+                "failed to read claude transcript {}: {e}",
+                transcript_path.display()
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut tool_uses: Vec<PendingToolUse> = Vec::new();
+    let mut calls = Vec::new();
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let msg_type = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(parse_iso_timestamp)
+            .unwrap_or(0);
+
+        match msg_type {
+            "assistant" => {
+                let content = entry.pointer("/message/content").and_then(|v| v.as_array());
+                if let Some(blocks) = content {
+                    for block in blocks {
+                        if block.get("type").and_then(|v| v.as_str()) == Some("tool_use") {
+                            let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                            let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                            let input = block
+                                .get("input")
+                                .cloned()
+                                .unwrap_or(serde_json::Value::Null);
+                            tool_uses.push(PendingToolUse {
+                                id: id.to_string(),
+                                tool: name.to_string(),
+                                input,
+                                timestamp,
+                            });
+                        }
+                    }
+                }
+            }
+            "user" => {
+                let content = entry.pointer("/message/content").and_then(|v| v.as_array());
+                if let Some(blocks) = content {
+                    for block in blocks {
+                        if block.get("type").and_then(|v| v.as_str()) == Some("tool_result") {
+                            let tool_use_id = block
+                                .get("tool_use_id")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("");
+                            let is_error = block
+                                .get("is_error")
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false);
+
+                            if let Some(pos) = tool_uses.iter().position(|t| t.id == tool_use_id) {
+                                let pending = tool_uses.remove(pos);
+                                let summary = summarize_input(&pending.tool, &pending.input);
+                                let duration_ms = if timestamp > pending.timestamp {
+                                    Some(timestamp - pending.timestamp)
+                                } else {
+                                    None
+                                };
+                                calls.push(ToolCall {
+                                    id: pending.id,
+                                    tool: pending.tool,
+                                    title: None,
+                                    input_summary: summary,
+                                    status: if is_error {
+                                        "error".to_string()
+                                    } else {
+                                        "completed".to_string()
+                                    },
+                                    timestamp: pending.timestamp,
+                                    duration_ms,
+                                    input_text: input_text(&pending.input),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // NOTE(victor): Pending tool_uses without results are still in-flight -- skip them
+    calls
+}
+
+pub fn read_claude_tool_detail(
+    transcript_path: &Path,
+    tool_use_id: &str,
+) -> Option<ToolCallDetail> {
+    let contents = match std::fs::read_to_string(transcript_path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(
+                "failed to read claude transcript {}: {e}",
+                transcript_path.display()
+            );
+            return None;
+        }
+    };
+
+    let mut detail: Option<ToolCallDetail> = None;
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let msg_type = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(parse_iso_timestamp)
+            .unwrap_or(0);
+        let Some(blocks) = entry.pointer("/message/content").and_then(|v| v.as_array()) else {
+            continue;
+        };
+
+        for block in blocks {
+            let block_type = block.get("type").and_then(|v| v.as_str());
+            match (msg_type, block_type) {
+                ("assistant", Some("tool_use")) if detail.is_none() => {
+                    if block.get("id").and_then(|v| v.as_str()) != Some(tool_use_id) {
+                        continue;
+                    }
+                    let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    detail = Some(ToolCallDetail {
+                        id: tool_use_id.to_string(),
+                        tool: name.to_string(),
+                        title: None,
+                        input: block
+                            .get("input")
+                            .cloned()
+                            .unwrap_or(serde_json::Value::Null),
+                        output: None,
+                        output_truncated: false,
+                        error: None,
+                        status: "running".to_string(),
+                        timestamp,
+                        duration_ms: None,
+                    });
+                }
+                ("user", Some("tool_result")) => {
+                    if block.get("tool_use_id").and_then(|v| v.as_str()) != Some(tool_use_id) {
+                        continue;
+                    }
+                    let Some(ref mut d) = detail else {
+                        continue;
+                    };
+                    let is_error = block
+                        .get("is_error")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+                    let text = block
+                        .get("content")
+                        .map(tool_result_text)
+                        .unwrap_or_default();
+                    if timestamp > d.timestamp {
+                        d.duration_ms = Some(timestamp - d.timestamp);
+                    }
+                    let (text, truncated) = truncate_output(text);
+                    d.output_truncated = truncated;
+                    if is_error {
+                        d.status = "error".to_string();
+                        d.error = Some(text);
+                    } else {
+                        d.status = "completed".to_string();
+                        d.output = Some(text);
+                    }
+                    return detail;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    detail
+}
+
+/// Flattens a `tool_result.content` value, which is either a plain string or
+/// an array of content blocks, into display text.
+fn tool_result_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .map(|b| match b.get("type").and_then(|v| v.as_str()) {
+                Some("text") => b
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                Some(other) => format!("[{other}]"),
+                None => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+pub fn read_claude_usage(transcript_path: &Path) -> TokenUsage {
+    let mut usage = TokenUsage::default();
+    let contents = match std::fs::read_to_string(transcript_path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(
+                "failed to read claude transcript {}: {e}",
+                transcript_path.display()
+            );
+            return usage;
+        }
+    };
+
+    // Claude writes one JSONL entry per content block, each repeating the same
+    // message.id. Streaming grows output_tokens across them, so the last entry wins.
+    let mut by_message: HashMap<String, TokenUsage> = HashMap::new();
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if entry.get("type").and_then(|v| v.as_str()) != Some("assistant") {
+            continue;
+        }
+        let Some(msg_usage) = entry.pointer("/message/usage") else {
+            continue;
+        };
+        let field = |name: &str| msg_usage.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        let mut msg = TokenUsage {
+            input_tokens: field("input_tokens"),
+            output_tokens: field("output_tokens"),
+            cache_read_tokens: field("cache_read_input_tokens"),
+            cache_write_tokens: field("cache_creation_input_tokens"),
+            cost_usd: 0.0,
+        };
+        let model = entry
+            .pointer("/message/model")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        msg.cost_usd = estimate_cost(model, &msg);
+        match entry.pointer("/message/id").and_then(|v| v.as_str()) {
+            Some(id) => {
+                by_message.insert(id.to_string(), msg);
+            }
+            None => usage.add(&msg),
+        }
+    }
+    for msg in by_message.values() {
+        usage.add(msg);
+    }
+    usage
+}
+
+pub fn read_claude_transcript(transcript_path: &Path) -> Vec<TranscriptMessage> {
+    let contents = match std::fs::read_to_string(transcript_path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(
+                "failed to read claude transcript {}: {e}",
+                transcript_path.display()
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut messages: Vec<TranscriptMessage> = Vec::new();
+    let mut last_message_id: Option<String> = None;
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let role = match entry.get("type").and_then(|v| v.as_str()) {
+            Some(r @ ("user" | "assistant")) => r,
+            _ => continue,
+        };
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(parse_iso_timestamp)
+            .unwrap_or(0);
+
+        let blocks = match entry.pointer("/message/content") {
+            Some(serde_json::Value::String(text)) => {
+                vec![TranscriptBlock::Text { text: text.clone() }]
+            }
+            Some(serde_json::Value::Array(blocks)) => {
+                blocks.iter().filter_map(claude_transcript_block).collect()
+            }
+            _ => Vec::new(),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        // Claude writes one entry per content block of the same API
+        // message. Merge them back so a turn reads as one message.
+        let message_id = entry
+            .pointer("/message/id")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        if message_id.is_some() && message_id == last_message_id {
+            if let Some(last) = messages.last_mut() {
+                last.blocks.extend(blocks);
+                continue;
+            }
+        }
+        last_message_id = message_id;
+
+        let id = entry
+            .get("uuid")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        messages.push(TranscriptMessage {
+            id,
+            role: role.to_string(),
+            timestamp,
+            blocks,
+        });
+    }
+
+    messages
+}
+
+fn claude_transcript_block(block: &serde_json::Value) -> Option<TranscriptBlock> {
+    let str_field = |name: &str| {
+        block
+            .get(name)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    match block.get("type").and_then(|v| v.as_str())? {
+        "text" => Some(TranscriptBlock::Text {
+            text: str_field("text"),
+        }),
+        "thinking" => Some(TranscriptBlock::Reasoning {
+            text: str_field("thinking"),
+        }),
+        "tool_use" => Some(TranscriptBlock::ToolUse {
+            id: str_field("id"),
+            tool: str_field("name"),
+        }),
+        "tool_result" => Some(TranscriptBlock::ToolResult {
+            tool_use_id: str_field("tool_use_id"),
+            is_error: block
+                .get("is_error")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        }),
+        _ => None,
+    }
+}
+
+struct PendingToolUse {
+    id: String,
+    tool: String,
+    input: serde_json::Value,
+    timestamp: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_claude_tools_from_jsonl() {
+        let jsonl = concat!(
+            r#"{"type":"assistant","timestamp":"2026-02-20T19:39:14.000Z","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}]},"uuid":"a1"}"#,
+            "\n",
+            r#"{"type":"user","timestamp":"2026-02-20T19:39:15.000Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"file1\nfile2"}]},"uuid":"a2"}"#,
+            "\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+
+        let calls = read_claude_tools(&path);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "toolu_1");
+        assert_eq!(calls[0].tool, "Bash");
+        assert_eq!(calls[0].input_summary, "ls");
+        assert_eq!(calls[0].status, "completed");
+        assert_eq!(calls[0].duration_ms, Some(1000));
+    }
+
+    #[test]
+    fn should_read_claude_tool_detail_from_jsonl() {
+        let long_cmd = "x".repeat(200);
+        let jsonl = format!(
+            "{}\n{}\n",
+            serde_json::json!({"type":"assistant","timestamp":"2026-02-20T19:39:14.000Z","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":long_cmd}}]}}),
+            serde_json::json!({"type":"user","timestamp":"2026-02-20T19:39:15.500Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":[{"type":"text","text":"line1"},{"type":"text","text":"line2"}]}]}}),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+
+        let detail = read_claude_tool_detail(&path, "toolu_1").unwrap();
+        assert_eq!(detail.tool, "Bash");
+        assert_eq!(detail.input["command"].as_str().unwrap().len(), 200);
+        assert_eq!(detail.output.as_deref(), Some("line1\nline2"));
+        assert_eq!(detail.status, "completed");
+        assert_eq!(detail.duration_ms, Some(1500));
+        assert!(read_claude_tool_detail(&path, "toolu_missing").is_none());
+    }
+
+    #[test]
+    fn should_truncate_claude_tool_errors() {
+        let stderr = "e".repeat(crate::toolcalls::MAX_TOOL_OUTPUT_BYTES + 10);
+        let jsonl = format!(
+            "{}\n{}\n",
+            serde_json::json!({"type":"assistant","timestamp":"2026-02-20T19:39:14.000Z","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"make"}}]}}),
+            serde_json::json!({"type":"user","timestamp":"2026-02-20T19:39:15.000Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","is_error":true,"content":stderr}]}}),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+
+        let detail = read_claude_tool_detail(&path, "toolu_1").unwrap();
+        assert_eq!(detail.status, "error");
+        assert_eq!(
+            detail.error.map(|e| e.len()),
+            Some(crate::toolcalls::MAX_TOOL_OUTPUT_BYTES)
+        );
+        assert!(detail.output_truncated);
+    }
+
+    #[test]
+    fn should_read_claude_usage_once_per_message_keeping_the_last_entry() {
+        let jsonl = concat!(
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000,"output_tokens":3,"cache_read_input_tokens":2000,"cache_creation_input_tokens":0},"content":[{"type":"text","text":"hi"}]}}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000,"output_tokens":500,"cache_read_input_tokens":2000,"cache_creation_input_tokens":0},"content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{}}]}}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"id":"msg_2","model":"claude-sonnet-4-5","usage":{"input_tokens":10,"output_tokens":5},"content":[]}}"#,
+            "\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+
+        let usage = read_claude_usage(&path);
+        assert_eq!(usage.input_tokens, 1010);
+        assert_eq!(usage.output_tokens, 505);
+        assert_eq!(usage.cache_read_tokens, 2000);
+        let expected = (1010.0 * 3.0 + 505.0 * 15.0 + 2000.0 * 0.30) / 1_000_000.0;
+        assert!((usage.cost_usd - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_read_claude_transcript_merging_split_messages() {
+        let jsonl = concat!(
+            r#"{"type":"user","uuid":"u1","timestamp":"2026-02-20T19:39:13.000Z","message":{"role":"user","content":"list files"}}"#,
+            "\n",
+            r#"{"type":"assistant","uuid":"a1","timestamp":"2026-02-20T19:39:14.000Z","message":{"id":"msg_1","role":"assistant","content":[{"type":"thinking","thinking":"use ls"}]}}"#,
+            "\n",
+            r#"{"type":"assistant","uuid":"a2","timestamp":"2026-02-20T19:39:14.100Z","message":{"id":"msg_1","role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}]}}"#,
+            "\n",
+            r#"{"type":"user","uuid":"u2","timestamp":"2026-02-20T19:39:15.000Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"a","is_error":true}]}}"#,
+            "\n",
+            r#"{"type":"summary","summary":"ignored"}"#,
+            "\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.jsonl");
+        std::fs::write(&path, jsonl).unwrap();
+
+        let messages = read_claude_transcript(&path);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, "user");
+        assert!(
+            matches!(&messages[0].blocks[0], TranscriptBlock::Text { text } if text == "list files")
+        );
+        assert_eq!(messages[1].id, "a1");
+        assert_eq!(messages[1].blocks.len(), 2);
+        assert!(
+            matches!(&messages[1].blocks[1], TranscriptBlock::ToolUse { id, .. } if id == "toolu_1")
+        );
+        assert!(matches!(
+            &messages[2].blocks[0],
+            TranscriptBlock::ToolResult { tool_use_id, is_error: true } if tool_use_id == "toolu_1"
+        ));
+    }
+
+    fn member<'a>(cwd: &'a str, agent_id: &'a str) -> MemberInfo<'a> {
+        MemberInfo {
+            team_name: "alpha",
+            name: "implementer",
+            agent_id,
+            cwd,
+            spawned_at: 0,
+            tmux_pane_id: None,
+            opencode_session_id: None,
+        }
+    }
+
+    #[test]
+    fn should_encode_project_dir_like_claude() {
+        assert_eq!(
+            encode_project_dir("/Users/me/my.app_v2"),
+            "-Users-me-my-app-v2"
+        );
+    }
+
+    #[test]
+    fn should_resolve_transcript_by_project_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects/-work-app");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        let lead = project_dir.join("lead.jsonl");
+        let worker = project_dir.join("worker.jsonl");
+        std::fs::write(&lead, r#"{"agentId":"team-lead@alpha"}"#).unwrap();
+        std::fs::write(&worker, r#"{"agentId":"implementer@alpha"}"#).unwrap();
+
+        let resolved =
+            resolve_via_project_scan(dir.path(), &member("/work/app", "implementer@alpha"));
+        assert_eq!(resolved, Some(worker));
+        assert_eq!(
+            resolve_via_project_scan(dir.path(), &member("/work/app", "reviewer@alpha")),
+            None
+        );
+    }
+
+    #[test]
+    fn should_detect_superseded_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let worker = member("/work/app", "implementer@alpha");
+        let old = dir.path().join("old.jsonl");
+        std::fs::write(&old, r#"{"agentId":"implementer@alpha"}"#).unwrap();
+        let session = SessionRef::Path(old.clone());
+        assert!(!ClaudeBackend.session_superseded(&worker, &session));
+
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        let write_later = |path: &Path, contents: &str| {
+            std::fs::write(path, contents).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(later)
+                .unwrap();
+        };
+        write_later(
+            &dir.path().join("lead.jsonl"),
+            r#"{"agentId":"team-lead@alpha"}"#,
+        );
+        assert!(!ClaudeBackend.session_superseded(&worker, &session));
+
+        write_later(
+            &dir.path().join("new.jsonl"),
+            r#"{"agentId":"implementer@alpha"}"#,
+        );
+        assert!(ClaudeBackend.session_superseded(&worker, &session));
+
+        std::fs::remove_file(&old).unwrap();
+        assert!(ClaudeBackend.session_superseded(&worker, &session));
+    }
+}
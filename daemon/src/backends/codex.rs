@@ -0,0 +1,765 @@
+//! Codex CLI backend: sessions are rollout JSONL files under `$CODEX_HOME/sessions/`.
+
+use super::{AgentBackend, MemberInfo, ResolvedSession, SessionRef};
+use crate::toolcalls::{
+    estimate_cost, input_text, parse_iso_timestamp, summarize_input, truncate_output, TokenUsage,
+    ToolCall, ToolCallDetail, TranscriptBlock, TranscriptMessage,
+};
+use std::path::{Path, PathBuf};
+
+pub struct CodexBackend;
+
+impl AgentBackend for CodexBackend {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn resolve_session(&self, member: &MemberInfo) -> Option<ResolvedSession> {
+        find_rollout(&codex_sessions_dir(), member).map(|(path, method)| ResolvedSession {
+            session: SessionRef::Path(path),
+            method,
+        })
+    }
+
+    fn read_tools(&self, session: &SessionRef) -> Vec<ToolCall> {
+        session.as_path().map(read_codex_tools).unwrap_or_default()
+    }
+
+    fn read_tool_detail(&self, session: &SessionRef, call_id: &str) -> Option<ToolCallDetail> {
+        read_codex_tool_detail(session.as_path()?, call_id)
+    }
+
+    fn read_transcript(&self, session: &SessionRef) -> Vec<TranscriptMessage> {
+        session
+            .as_path()
+            .map(read_codex_transcript)
+            .unwrap_or_default()
+    }
+
+    fn read_usage(&self, session: &SessionRef) -> TokenUsage {
+        session.as_path().map(read_codex_usage).unwrap_or_default()
+    }
+
+    fn is_live(&self, session: &SessionRef) -> bool {
+        session
+            .as_path()
+            .map(super::file_recently_modified)
+            .unwrap_or(false)
+    }
+}
+
+// --- Session resolution ---
+
+fn codex_sessions_dir() -> PathBuf {
+    let codex_home = std::env::var("CODEX_HOME").unwrap_or_else(|_| {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+        format!("{home}/.codex")
+    });
+    PathBuf::from(codex_home).join("sessions")
+}
+
+/// How far into a rollout to look for the member's agent id.
+const AGENT_ID_HEAD_BYTES: u64 = 64 * 1024;
+
+/// Rollouts can start a little before the team config records the member.
+const SPAWN_SLACK_MS: u64 = 60_000;
+
+/// Finds the member's rollout among those whose `session_meta.cwd` matches its
+/// working directory. Several members can share a cwd, so the cwd alone isn't
+/// enough: prefer the newest rollout that mentions the member's agent id, else
+/// the one that started closest after the member was spawned. Rollouts are stored
+/// as `sessions/YYYY/MM/DD/rollout-*.jsonl`; day dirs before the spawn are skipped.
+fn find_rollout(sessions_dir: &Path, member: &MemberInfo) -> Option<(PathBuf, &'static str)> {
+    if member.cwd.is_empty() {
+        return None;
+    }
+    let since_day = (member.spawned_at > 0).then(|| day_dir(member.spawned_at));
+    let mut rollouts = Vec::new();
+    collect_rollouts(sessions_dir, "", since_day.as_deref(), &mut rollouts);
+
+    let candidates: Vec<(PathBuf, RolloutMeta)> = rollouts
+        .into_iter()
+        .filter_map(|path| Some((rollout_meta(&path)?, path)))
+        .filter(|(meta, _)| meta.cwd == member.cwd)
+        .map(|(meta, path)| (path, meta))
+        .collect();
+
+    if !member.agent_id.is_empty() {
+        let by_agent_id = candidates
+            .iter()
+            .filter(|(path, meta)| {
+                meta.id == member.agent_id
+                    || mentions(
+                        &super::read_head(path, AGENT_ID_HEAD_BYTES),
+                        member.agent_id,
+                    )
+            })
+            .max_by_key(|(_, meta)| meta.started_at);
+        if let Some((path, _)) = by_agent_id {
+            return Some((path.clone(), "agent-id"));
+        }
+    }
+
+    if member.spawned_at > 0 {
+        let earliest = member.spawned_at.saturating_sub(SPAWN_SLACK_MS);
+        return candidates
+            .into_iter()
+            .filter(|(_, meta)| meta.started_at >= earliest)
+            .min_by_key(|(_, meta)| meta.started_at.abs_diff(member.spawned_at))
+            .map(|(path, _)| (path, "spawn-time"));
+    }
+
+    candidates
+        .into_iter()
+        .max_by_key(|(_, meta)| meta.started_at)
+        .map(|(path, _)| (path, "cwd"))
+}
+
+/// True if `id` appears in `text` as a whole word, so `lead@alpha` doesn't match
+/// inside `team-lead@alpha`.
+fn mentions(text: &str, id: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '-' || c == '_' || c == '@';
+    text.match_indices(id).any(|(at, _)| {
+        !text[..at].chars().next_back().is_some_and(is_word)
+            && !text[at + id.len()..].chars().next().is_some_and(is_word)
+    })
+}
+
+/// `YYYY/MM/DD` of the day before `at_ms` in UTC. Codex names day dirs in local
+/// time, so a day of slack covers any offset.
+fn day_dir(at_ms: u64) -> String {
+    let at = time::OffsetDateTime::from_unix_timestamp((at_ms / 1000) as i64)
+        .unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
+        - time::Duration::days(1);
+    format!("{:04}/{:02}/{:02}", at.year(), at.month() as u8, at.day())
+}
+
+/// Walks `dir` for rollouts. `rel` is `dir` relative to the sessions root; any
+/// directory that sorts before the same-length prefix of `since_day` is skipped.
+fn collect_rollouts(dir: &Path, rel: &str, since_day: Option<&str>, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let child = if rel.is_empty() {
+                name
+            } else {
+                format!("{rel}/{name}")
+            };
+            if let Some(since) = since_day {
+                if since
+                    .get(..child.len())
+                    .is_some_and(|prefix| *child < *prefix)
+                {
+                    continue;
+                }
+            }
+            collect_rollouts(&path, &child, since_day, out);
+        } else if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with("rollout-") && n.ends_with(".jsonl"))
+            .unwrap_or(false)
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// The `session_meta` line every rollout starts with.
+struct RolloutMeta {
+    id: String,
+    cwd: String,
+    started_at: u64,
+}
+
+fn rollout_meta(path: &Path) -> Option<RolloutMeta> {
+    use std::io::BufRead;
+    let file = std::fs::File::open(path).ok()?;
+    let first = std::io::BufReader::new(file).lines().next()?.ok()?;
+    let entry: serde_json::Value = serde_json::from_str(&first).ok()?;
+    let meta = entry.get("payload").unwrap_or(&entry);
+    let field = |key: &str| meta.get(key).and_then(|v| v.as_str());
+    Some(RolloutMeta {
+        id: field("id").unwrap_or_default().to_string(),
+        cwd: field("cwd")?.to_string(),
+        started_at: field("timestamp")
+            .or_else(|| entry.get("timestamp").and_then(|v| v.as_str()))
+            .and_then(parse_iso_timestamp)
+            .unwrap_or(0),
+    })
+}
+
+// --- Rollout parsing ---
+
+/// One rollout line, unwrapped. Newer Codex versions write
+/// `{"timestamp", "type", "payload": {...}}`; older ones write the item directly.
+struct RolloutItem {
+    timestamp: u64,
+    kind: String,
+    payload: serde_json::Value,
+}
+
+fn read_rollout(path: &Path) -> Vec<RolloutItem> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("failed to read codex rollout {}: {e}", path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut items = Vec::new();
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+        let mut entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let timestamp = entry
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(parse_iso_timestamp)
+            .unwrap_or(0);
+        let (kind, payload) = match entry.get_mut("payload").map(serde_json::Value::take) {
+            Some(payload) => {
+                let outer = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                let inner = payload.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                // Response items carry the interesting type inside the
+                // payload; session_meta / turn_context only have the outer one.
+                let kind = if outer == "response_item" || outer == "event_msg" {
+                    inner
+                } else {
+                    outer
+                };
+                (kind.to_string(), payload)
+            }
+            None => {
+                let kind = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                (kind.to_string(), entry)
+            }
+        };
+        items.push(RolloutItem {
+            timestamp,
+            kind,
+            payload,
+        });
+    }
+    items
+}
+
+/// Maps a Codex call to our tool vocabulary. `shell` / `local_shell_call` become
+/// `bash` with a flat command string so summaries and stats line up with Claude.
+fn normalize_call(item: &RolloutItem) -> Option<(String, String, serde_json::Value)> {
+    let call_id = item.payload.get("call_id").and_then(|v| v.as_str())?;
+    let (tool, input) = match item.kind.as_str() {
+        "function_call" => {
+            let name = item.payload.get("name").and_then(|v| v.as_str())?;
+            let args = item
+                .payload
+                .get("arguments")
+                .and_then(|v| v.as_str())
+                .and_then(|s| serde_json::from_str(s).ok())
+                .unwrap_or(serde_json::Value::Null);
+            if name == "shell" {
+                ("bash".to_string(), shell_input(&args))
+            } else {
+                (name.to_string(), args)
+            }
+        }
+        "local_shell_call" => {
+            let action = item
+                .payload
+                .get("action")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            ("bash".to_string(), shell_input(&action))
+        }
+        "custom_tool_call" => {
+            let name = item.payload.get("name").and_then(|v| v.as_str())?;
+            let input = item
+                .payload
+                .get("input")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+            (name.to_string(), serde_json::json!({ "input": input }))
+        }
+        _ => return None,
+    };
+    Some((call_id.to_string(), tool, input))
+}
+
+fn shell_input(args: &serde_json::Value) -> serde_json::Value {
+    let command = match args.get("command") {
+        Some(serde_json::Value::Array(parts)) => {
+            let parts: Vec<&str> = parts.iter().filter_map(|p| p.as_str()).collect();
+            // Codex wraps most commands as ["bash", "-lc", "<script>"].
+            match parts.as_slice() {
+                [_, "-lc", script] | [_, "-c", script] => script.to_string(),
+                _ => parts.join(" "),
+            }
+        }
+        Some(serde_json::Value::String(s)) => s.clone(),
+        _ => String::new(),
+    };
+    let mut input = serde_json::json!({ "command": command });
+    if let Some(workdir) = args.get("workdir") {
+        input["workdir"] = workdir.clone();
+    }
+    input
+}
+
+struct CallOutput {
+    text: String,
+    is_error: bool,
+    duration_ms: Option<u64>,
+}
+
+/// Exec outputs are a JSON string `{"output", "metadata": {"exit_code", "duration_seconds"}}`;
+/// other tools return plain text.
+fn parse_call_output(item: &RolloutItem) -> CallOutput {
+    let raw = match item.payload.get("output") {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
+        return CallOutput {
+            text: raw,
+            is_error: false,
+            duration_ms: None,
+        };
+    };
+    let text = parsed
+        .get("output")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .unwrap_or(raw);
+    let exit_code = parsed
+        .pointer("/metadata/exit_code")
+        .and_then(|v| v.as_i64());
+    let duration_ms = parsed
+        .pointer("/metadata/duration_seconds")
+        .and_then(|v| v.as_f64())
+        .map(|s| (s * 1000.0).round() as u64);
+    CallOutput {
+        text,
+        is_error: exit_code.map(|c| c != 0).unwrap_or(false),
+        duration_ms,
+    }
+}
+
+fn is_call_output(kind: &str) -> bool {
+    matches!(kind, "function_call_output" | "custom_tool_call_output")
+}
+
+// --- Readers ---
+
+pub fn read_codex_tools(rollout_path: &Path) -> Vec<ToolCall> {
+    let items = read_rollout(rollout_path);
+    let mut pending: Vec<(String, String, serde_json::Value, u64)> = Vec::new();
+    let mut calls = Vec::new();
+
+    for item in &items {
+        if let Some((id, tool, input)) = normalize_call(item) {
+            pending.push((id, tool, input, item.timestamp));
+            continue;
+        }
+        if !is_call_output(&item.kind) {
+            continue;
+        }
+        let Some(call_id) = item.payload.get("call_id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let Some(pos) = pending.iter().position(|(id, ..)| id == call_id) else {
+            continue;
+        };
+        let (id, tool, input, started) = pending.remove(pos);
+        let output = parse_call_output(item);
+        let duration_ms = output
+            .duration_ms
+            .or_else(|| (item.timestamp > started).then(|| item.timestamp - started));
+        calls.push(ToolCall {
+            id,
+            input_summary: summarize_input(&tool, &input),
+            input_text: input_text(&input),
+            tool,
+            title: None,
+            status: if output.is_error {
+                "error".to_string()
+            } else {
+                "completed".to_string()
+            },
+            timestamp: started,
+            duration_ms,
+        });
+    }
+
+    // Calls without an output are still in-flight -- skip them, same as Claude.
+    calls
+}
+
+pub fn read_codex_tool_detail(rollout_path: &Path, call_id: &str) -> Option<ToolCallDetail> {
+    let items = read_rollout(rollout_path);
+    let mut detail: Option<ToolCallDetail> = None;
+
+    for item in &items {
+        if detail.is_none() {
+            if let Some((id, tool, input)) = normalize_call(item) {
+                if id == call_id {
+                    detail = Some(ToolCallDetail {
+                        id,
+                        tool,
+                        title: None,
+                        input,
+                        output: None,
+                        output_truncated: false,
+                        error: None,
+                        status: "running".to_string(),
+                        timestamp: item.timestamp,
+                        duration_ms: None,
+                    });
+                }
+            }
+            continue;
+        }
+        if !is_call_output(&item.kind)
+            || item.payload.get("call_id").and_then(|v| v.as_str()) != Some(call_id)
+        {
+            continue;
+        }
+        let d = detail.as_mut()?;
+        let output = parse_call_output(item);
+        d.duration_ms = output
+            .duration_ms
+            .or_else(|| (item.timestamp > d.timestamp).then(|| item.timestamp - d.timestamp));
+        let (text, truncated) = truncate_output(output.text);
+        if output.is_error {
+            d.status = "error".to_string();
+            d.error = Some(text.clone());
+        } else {
+            d.status = "completed".to_string();
+        }
+        d.output = Some(text);
+        d.output_truncated = truncated;
+        break;
+    }
+
+    detail
+}
+
+pub fn read_codex_transcript(rollout_path: &Path) -> Vec<TranscriptMessage> {
+    let items = read_rollout(rollout_path);
+    let mut messages: Vec<TranscriptMessage> = Vec::new();
+
+    for (index, item) in items.iter().enumerate() {
+        let (role, blocks) = match item.kind.as_str() {
+            "message" => {
+                let role = item
+                    .payload
+                    .get("role")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let blocks = item
+                    .payload
+                    .get("content")
+                    .and_then(|v| v.as_array())
+                    .map(|content| {
+                        content
+                            .iter()
+                            .filter_map(|c| c.get("text").and_then(|v| v.as_str()))
+                            .map(|text| TranscriptBlock::Text {
+                                text: text.to_string(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (role.to_string(), blocks)
+            }
+            "reasoning" => {
+                let blocks = item
+                    .payload
+                    .get("summary")
+                    .and_then(|v| v.as_array())
+                    .map(|summary| {
+                        summary
+                            .iter()
+                            .filter_map(|c| c.get("text").and_then(|v| v.as_str()))
+                            .map(|text| TranscriptBlock::Reasoning {
+                                text: text.to_string(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                ("assistant".to_string(), blocks)
+            }
+            kind if is_call_output(kind) => {
+                let tool_use_id = item
+                    .payload
+                    .get("call_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let is_error = parse_call_output(item).is_error;
+                (
+                    "user".to_string(),
+                    vec![TranscriptBlock::ToolResult {
+                        tool_use_id,
+                        is_error,
+                    }],
+                )
+            }
+            _ => match normalize_call(item) {
+                Some((id, tool, _)) => (
+                    "assistant".to_string(),
+                    vec![TranscriptBlock::ToolUse { id, tool }],
+                ),
+                None => continue,
+            },
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+
+        // Codex writes reasoning, calls and text as separate items.
+        // Fold consecutive same-role items into one message, like Claude's split entries.
+        if let Some(last) = messages.last_mut() {
+            if last.role == role {
+                last.blocks.extend(blocks);
+                continue;
+            }
+        }
+        messages.push(TranscriptMessage {
+            id: index.to_string(),
+            role,
+            timestamp: item.timestamp,
+            blocks,
+        });
+    }
+
+    messages
+}
+
+/// `token_count` events carry cumulative totals, so the last one wins.
+pub fn read_codex_usage(rollout_path: &Path) -> TokenUsage {
+    let items = read_rollout(rollout_path);
+    let mut model = String::new();
+    let mut usage = TokenUsage::default();
+
+    for item in &items {
+        match item.kind.as_str() {
+            "turn_context" => {
+                if let Some(m) = item.payload.get("model").and_then(|v| v.as_str()) {
+                    model = m.to_string();
+                }
+            }
+            "token_count" => {
+                let Some(total) = item.payload.pointer("/info/total_token_usage") else {
+                    continue;
+                };
+                let field = |name: &str| total.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+                // Codex counts cached tokens inside input_tokens.
+                let cached = field("cached_input_tokens");
+                usage = TokenUsage {
+                    input_tokens: field("input_tokens").saturating_sub(cached),
+                    output_tokens: field("output_tokens"),
+                    cache_read_tokens: cached,
+                    cache_write_tokens: 0,
+                    cost_usd: 0.0,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    usage.cost_usd = estimate_cost(&model, &usage);
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/codex-rollout.jsonl");
+
+    fn write_fixture(dir: &Path) -> PathBuf {
+        let path = dir.join("sessions/2026/02/20/rollout-2026-02-20T19-39-10-0199a1b2.jsonl");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, FIXTURE).unwrap();
+        path
+    }
+
+    #[test]
+    fn should_read_codex_tools_from_rollout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let calls = read_codex_tools(&path);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_shell_1");
+        assert_eq!(calls[0].tool, "bash");
+        assert_eq!(
+            calls[0].input_summary,
+            "cargo test --manifest-path daemon/Cargo.toml"
+        );
+        assert_eq!(calls[0].status, "completed");
+        assert_eq!(calls[0].duration_ms, Some(7900));
+        assert_eq!(calls[1].tool, "apply_patch");
+        assert_eq!(calls[1].status, "error");
+    }
+
+    #[test]
+    fn should_read_codex_tool_detail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let detail = read_codex_tool_detail(&path, "call_patch_1").unwrap();
+        assert_eq!(detail.status, "error");
+        assert_eq!(detail.error.as_deref(), Some("error: patch did not apply"));
+        assert!(detail.input["input"]
+            .as_str()
+            .unwrap()
+            .contains("daemon/src/main.rs"));
+
+        let running = read_codex_tool_detail(&path, "call_shell_2").unwrap();
+        assert_eq!(running.status, "running");
+        assert_eq!(running.input["command"], "rg mod config");
+    }
+
+    #[test]
+    fn should_read_codex_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let messages = read_codex_transcript(&path);
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(
+            roles,
+            [
+                "user",
+                "assistant",
+                "user",
+                "assistant",
+                "user",
+                "assistant"
+            ]
+        );
+        assert!(matches!(
+            &messages[1].blocks[0],
+            TranscriptBlock::Reasoning { .. }
+        ));
+        assert!(
+            matches!(&messages[1].blocks[1], TranscriptBlock::ToolUse { id, tool } if id == "call_shell_1" && tool == "bash")
+        );
+        assert!(matches!(
+            messages[5].blocks.last(),
+            Some(TranscriptBlock::Text { text }) if text.starts_with("Tests pass")
+        ));
+    }
+
+    #[test]
+    fn should_read_cumulative_codex_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path());
+
+        let usage = read_codex_usage(&path);
+        assert_eq!(usage.input_tokens, 5000);
+        assert_eq!(usage.cache_read_tokens, 10000);
+        assert_eq!(usage.output_tokens, 900);
+        assert!(usage.cost_usd > 0.0);
+    }
+
+    fn member<'a>(cwd: &'a str, agent_id: &'a str, spawned_at: u64) -> MemberInfo<'a> {
+        MemberInfo {
+            team_name: "alpha",
+            name: "implementer",
+            agent_id,
+            cwd,
+            spawned_at,
+            tmux_pane_id: None,
+            opencode_session_id: None,
+        }
+    }
+
+    fn write_rollout(dir: &Path, day: &str, started: &str, prompt: &str) -> PathBuf {
+        let path = dir.join(format!("sessions/{day}/rollout-{started}.jsonl"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let meta = serde_json::json!({
+            "timestamp": started,
+            "type": "session_meta",
+            "payload": {"id": format!("id-{started}"), "timestamp": started, "cwd": "/work/shared"},
+        });
+        let message = serde_json::json!({
+            "timestamp": started,
+            "type": "response_item",
+            "payload": {"type": "message", "role": "user", "content": [{"type": "input_text", "text": prompt}]},
+        });
+        std::fs::write(&path, format!("{meta}\n{message}\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn should_find_rollout_for_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path());
+        let sessions = dir.path().join("sessions");
+
+        assert_eq!(
+            find_rollout(&sessions, &member("/work/nightshift", "", 0)),
+            Some((path, "cwd"))
+        );
+        assert_eq!(find_rollout(&sessions, &member("/work/other", "", 0)), None);
+    }
+
+    #[test]
+    fn should_tell_members_sharing_a_cwd_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions = dir.path().join("sessions");
+        let lead = write_rollout(
+            dir.path(),
+            "2026/03/01",
+            "2026-03-01T10:00:00.000Z",
+            "you are team-lead@alpha",
+        );
+        let worker = write_rollout(
+            dir.path(),
+            "2026/03/01",
+            "2026-03-01T10:05:00.000Z",
+            "you are implementer@alpha, not lead@alpha",
+        );
+
+        let found = find_rollout(&sessions, &member("/work/shared", "implementer@alpha", 0));
+        assert_eq!(found, Some((worker.clone(), "agent-id")));
+        let found = find_rollout(&sessions, &member("/work/shared", "team-lead@alpha", 0));
+        assert_eq!(found, Some((lead.clone(), "agent-id")));
+        let found = find_rollout(&sessions, &member("/work/shared", "lead@alpha", 0));
+        assert_eq!(found, Some((worker.clone(), "agent-id")));
+
+        let lead_spawned = parse_iso_timestamp("2026-03-01T09:59:58.000Z").unwrap();
+        let found = find_rollout(&sessions, &member("/work/shared", "", lead_spawned));
+        assert_eq!(found, Some((lead, "spawn-time")));
+        let worker_spawned = parse_iso_timestamp("2026-03-01T10:04:59.000Z").unwrap();
+        let found = find_rollout(&sessions, &member("/work/shared", "", worker_spawned));
+        assert_eq!(found, Some((worker, "spawn-time")));
+    }
+
+    #[test]
+    fn should_skip_day_dirs_before_spawn() {
+        let dir = tempfile::tempdir().unwrap();
+        let sessions = dir.path().join("sessions");
+        write_rollout(dir.path(), "2025/12/31", "2025-12-31T10:00:00.000Z", "old");
+        write_rollout(dir.path(), "2026/02/27", "2026-02-27T10:00:00.000Z", "old");
+        let recent = write_rollout(dir.path(), "2026/03/01", "2026-03-01T10:00:00.000Z", "new");
+
+        let mut rollouts = Vec::new();
+        let since = day_dir(parse_iso_timestamp("2026-03-01T09:00:00.000Z").unwrap());
+        assert_eq!(since, "2026/02/28");
+        collect_rollouts(&sessions, "", Some(&since), &mut rollouts);
+        assert_eq!(rollouts, [recent]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Agent backends: how to find a team member's session and read tools, transcript
+//! and usage from it. Adding an agent CLI is one module plus an entry in `BACKENDS`.
+
+pub mod claude;
+pub mod codex;
+pub mod opencode;
+
+use crate::toolcalls::{TokenUsage, ToolCall, ToolCallDetail, TranscriptMessage};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Backend assumed when a member config has no `backendType`.
+pub const DEFAULT_BACKEND: &str = "claude";
+
+/// A session counts as live if it was written to within this window.
+pub const LIVE_WINDOW: Duration = Duration::from_secs(120);
+
+static BACKENDS: &[&dyn AgentBackend] = &[
+    &claude::ClaudeBackend,
+    &opencode::OpenCodeBackend,
+    &codex::CodexBackend,
+];
+
+/// The subset of a team member's config that backends use to find its session.
+pub struct MemberInfo<'a> {
+    pub team_name: &'a str,
+    pub name: &'a str,
+    pub agent_id: &'a str,
+    pub cwd: &'a str,
+    /// When the member joined the team (epoch ms), else the team's `createdAt`; 0 if unknown.
+    pub spawned_at: u64,
+    pub tmux_pane_id: Option<&'a str>,
+    pub opencode_session_id: Option<&'a str>,
+}
+
+/// A resolved session handle. File-backed agents store transcripts on disk,
+/// database-backed agents are addressed by session id.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionRef {
+    Path(PathBuf),
+    Id(String),
+}
+
+impl SessionRef {
+    pub fn as_path(&self) -> Option<&Path> {
+        match self {
+            SessionRef::Path(p) => Some(p),
+            SessionRef::Id(_) => None,
+        }
+    }
+
+    pub fn as_id(&self) -> Option<&str> {
+        match self {
+            SessionRef::Id(id) => Some(id),
+            SessionRef::Path(_) => None,
+        }
+    }
+}
+
+/// A session plus how it was found, e.g. `"tmux"` or `"project-scan"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedSession {
+    pub session: SessionRef,
+    pub method: &'static str,
+}
+
+pub trait AgentBackend: Send + Sync {
+    /// Matches `MemberConfig.backend_type`.
+    fn name(&self) -> &'static str;
+
+    fn resolve_session(&self, member: &MemberInfo) -> Option<ResolvedSession>;
+
+    /// True if the member's session may have been replaced (e.g. a new transcript
+    /// after `/clear`) and should be resolved again. Deleted or truncated session
+    /// files are handled by the caller.
+    fn session_superseded(&self, _member: &MemberInfo, _session: &SessionRef) -> bool {
+        false
+    }
+
+    fn read_tools(&self, session: &SessionRef) -> Vec<ToolCall>;
+
+    fn read_tool_detail(&self, session: &SessionRef, call_id: &str) -> Option<ToolCallDetail>;
+
+    fn read_transcript(&self, session: &SessionRef) -> Vec<TranscriptMessage>;
+
+    fn read_usage(&self, session: &SessionRef) -> TokenUsage;
+
+    /// True if the session has seen activity within `LIVE_WINDOW`.
+    fn is_live(&self, session: &SessionRef) -> bool;
+}
+
+pub fn get(name: &str) -> Option<&'static dyn AgentBackend> {
+    BACKENDS.iter().copied().find(|b| b.name() == name)
+}
+
+/// Liveness check shared by file-backed backends: was the file modified recently?
+pub fn file_recently_modified(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .map(|age| age < LIVE_WINDOW)
+        .unwrap_or(false)
+}
+
+/// Up to `limit` bytes from the start of a file, lossily decoded.
+pub fn read_head(path: &Path, limit: u64) -> String {
+    use std::io::Read;
+    let mut buf = Vec::new();
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file.take(limit).read_to_end(&mut buf);
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_look_up_registered_backends() {
+        assert_eq!(get("claude").unwrap().name(), "claude");
+        assert_eq!(get("opencode").unwrap().name(), "opencode");
+        assert_eq!(get("codex").unwrap().name(), "codex");
+        assert!(get("unknown").is_none());
+    }
+}
@@ -0,0 +1,640 @@
+//! OpenCode backend: sessions live in opencode's SQLite database, addressed by
+//! the `opencodeSessionId` recorded in the member config.
+
+use super::{AgentBackend, MemberInfo, ResolvedSession, SessionRef, LIVE_WINDOW};
+use crate::toolcalls::{
+    input_text, summarize_input, truncate_output, TokenUsage, ToolCall, ToolCallDetail,
+    TranscriptBlock, TranscriptMessage,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct OpenCodeBackend;
+
+impl AgentBackend for OpenCodeBackend {
+    fn name(&self) -> &'static str {
+        "opencode"
+    }
+
+    fn resolve_session(&self, member: &MemberInfo) -> Option<ResolvedSession> {
+        member.opencode_session_id.map(|id| ResolvedSession {
+            session: SessionRef::Id(id.to_string()),
+            method: "config",
+        })
+    }
+
+    fn session_superseded(&self, member: &MemberInfo, session: &SessionRef) -> bool {
+        member.opencode_session_id != session.as_id()
+    }
+
+    fn read_tools(&self, session: &SessionRef) -> Vec<ToolCall> {
+        session.as_id().map(read_opencode_tools).unwrap_or_default()
+    }
+
+    fn read_tool_detail(&self, session: &SessionRef, call_id: &str) -> Option<ToolCallDetail> {
+        read_opencode_tool_detail(session.as_id()?, call_id)
+    }
+
+    fn read_transcript(&self, session: &SessionRef) -> Vec<TranscriptMessage> {
+        session
+            .as_id()
+            .map(read_opencode_transcript)
+            .unwrap_or_default()
+    }
+
+    fn read_usage(&self, session: &SessionRef) -> TokenUsage {
+        session.as_id().map(read_opencode_usage).unwrap_or_default()
+    }
+
+    fn is_live(&self, session: &SessionRef) -> bool {
+        let Some(session_id) = session.as_id() else {
+            return false;
+        };
+        let Some(conn) = open_opencode_db() else {
+            return false;
+        };
+        let latest: Option<u64> = conn
+            .query_row(
+                "SELECT MAX(time_created) FROM part WHERE session_id = ?1",
+                [session_id],
+                |row| row.get(0),
+            )
+            .ok()
+            .flatten();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        latest
+            .map(|t| now.saturating_sub(t) < LIVE_WINDOW.as_millis() as u64)
+            .unwrap_or(false)
+    }
+}
+
+fn open_opencode_db() -> Option<rusqlite::Connection> {
+    let db_path = opencode_db_path()?;
+    if !db_path.exists() {
+        return None;
+    }
+
+    match rusqlite::Connection::open_with_flags(
+        &db_path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    ) {
+        Ok(c) => Some(c),
+        Err(e) => {
+            tracing::warn!("failed to open opencode db: {e}");
+            None
+        }
+    }
+}
+
+pub fn read_opencode_tools(session_id: &str) -> Vec<ToolCall> {
+    let Some(conn) = open_opencode_db() else {
+        return Vec::new();
+    };
+
+    let mut stmt = match conn.prepare(
+        "SELECT id, data, time_created FROM part \
+         WHERE session_id = ?1 \
+         AND json_extract(data, '$.type') = 'tool' \
+         AND json_extract(data, '$.state.status') IN ('completed', 'error') \
+         ORDER BY time_created",
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("failed to prepare opencode query: {e}");
+            return Vec::new();
+        }
+    };
+
+    let rows = match stmt.query_map([session_id], |row| {
+        let id: String = row.get(0)?;
+        let data: String = row.get(1)?;
+        let time_created: u64 = row.get(2)?;
+        Ok((id, data, time_created))
+    }) {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("failed to query opencode tools: {e}");
+            return Vec::new();
+        }
+    };
+
+    let mut calls = Vec::new();
+    for row in rows {
+        let Ok((id, data, time_created)) = row else {
+            continue;
+        };
+        if let Some(call) = parse_opencode_part(&id, &data, time_created) {
+            calls.push(call);
+        }
+    }
+    calls
+}
+
+/// Finished tool parts touched at or after `since` (epoch ms), plus the new high-water
+/// mark. Parts updated in the high-water millisecond come back again on the next call,
+/// since another may still land in it; callers dedupe by part id.
+///
+/// Keyed on `time_updated`, not `time_created` -- a tool part is inserted
+/// when the call starts and only updated to completed/error when it finishes.
+pub fn read_opencode_tools_since(session_id: &str, since: u64) -> (Vec<ToolCall>, u64) {
+    let Some(conn) = open_opencode_db() else {
+        return (Vec::new(), since);
+    };
+    match query_tools_since(&conn, session_id, since) {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("failed to query new opencode tools: {e}");
+            (Vec::new(), since)
+        }
+    }
+}
+
+fn query_tools_since(
+    conn: &rusqlite::Connection,
+    session_id: &str,
+    since: u64,
+) -> rusqlite::Result<(Vec<ToolCall>, u64)> {
+    let mut stmt = conn.prepare(
+        "SELECT id, data, time_created, time_updated FROM part \
+         WHERE session_id = ?1 AND time_updated >= ?2 \
+         AND json_extract(data, '$.type') = 'tool' \
+         AND json_extract(data, '$.state.status') IN ('completed', 'error') \
+         ORDER BY time_created",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![session_id, since], |row| {
+        let id: String = row.get(0)?;
+        let data: String = row.get(1)?;
+        let time_created: u64 = row.get(2)?;
+        let time_updated: u64 = row.get(3)?;
+        Ok((id, data, time_created, time_updated))
+    })?;
+
+    let mut calls = Vec::new();
+    let mut high_water = since;
+    for (id, data, time_created, time_updated) in rows.flatten() {
+        high_water = high_water.max(time_updated);
+        if let Some(call) = parse_opencode_part(&id, &data, time_created) {
+            calls.push(call);
+        }
+    }
+    Ok((calls, high_water))
+}
+
+pub fn read_opencode_tool_detail(session_id: &str, part_id: &str) -> Option<ToolCallDetail> {
+    let conn = open_opencode_db()?;
+    let row = conn.query_row(
+        "SELECT data, time_created FROM part \
+         WHERE session_id = ?1 AND id = ?2 \
+         AND json_extract(data, '$.type') = 'tool'",
+        [session_id, part_id],
+        |row| {
+            let data: String = row.get(0)?;
+            let time_created: u64 = row.get(1)?;
+            Ok((data, time_created))
+        },
+    );
+    match row {
+        Ok((data, time_created)) => parse_opencode_part_detail(part_id, &data, time_created),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => {
+            tracing::warn!("failed to query opencode tool detail: {e}");
+            None
+        }
+    }
+}
+
+pub fn read_opencode_usage(session_id: &str) -> TokenUsage {
+    let mut usage = TokenUsage::default();
+    let Some(conn) = open_opencode_db() else {
+        return usage;
+    };
+
+    let mut stmt = match conn.prepare(
+        "SELECT data FROM message \
+         WHERE session_id = ?1 \
+         AND json_extract(data, '$.role') = 'assistant'",
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("failed to prepare opencode usage query: {e}");
+            return usage;
+        }
+    };
+
+    let rows = match stmt.query_map([session_id], |row| row.get::<_, String>(0)) {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("failed to query opencode usage: {e}");
+            return usage;
+        }
+    };
+
+    for data in rows.flatten() {
+        if let Some(msg_usage) = parse_opencode_message_usage(&data) {
+            usage.add(&msg_usage);
+        }
+    }
+    usage
+}
+
+pub fn read_opencode_transcript(session_id: &str) -> Vec<TranscriptMessage> {
+    let Some(conn) = open_opencode_db() else {
+        return Vec::new();
+    };
+
+    let mut messages = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    let mut stmt = match conn.prepare(
+        "SELECT id, time_created, json_extract(data, '$.role') FROM message \
+         WHERE session_id = ?1 ORDER BY time_created",
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("failed to prepare opencode transcript query: {e}");
+            return Vec::new();
+        }
+    };
+    let rows = stmt.query_map([session_id], |row| {
+        let id: String = row.get(0)?;
+        let time_created: u64 = row.get(1)?;
+        let role: Option<String> = row.get(2)?;
+        Ok((id, time_created, role))
+    });
+    match rows {
+        Ok(rows) => {
+            for (id, time_created, role) in rows.flatten() {
+                index.insert(id.clone(), messages.len());
+                messages.push(TranscriptMessage {
+                    id,
+                    role: role.unwrap_or_default(),
+                    timestamp: time_created,
+                    blocks: Vec::new(),
+                });
+            }
+        }
+        Err(e) => {
+            tracing::warn!("failed to query opencode messages: {e}");
+            return Vec::new();
+        }
+    }
+
+    let mut stmt = match conn.prepare(
+        "SELECT id, message_id, data FROM part \
+         WHERE session_id = ?1 ORDER BY time_created",
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("failed to prepare opencode parts query: {e}");
+            return messages;
+        }
+    };
+    let rows = stmt.query_map([session_id], |row| {
+        let id: String = row.get(0)?;
+        let message_id: String = row.get(1)?;
+        let data: String = row.get(2)?;
+        Ok((id, message_id, data))
+    });
+    match rows {
+        Ok(rows) => {
+            for (id, message_id, data) in rows.flatten() {
+                let Some(&i) = index.get(&message_id) else {
+                    continue;
+                };
+                messages[i]
+                    .blocks
+                    .extend(parse_opencode_transcript_part(&id, &data));
+            }
+        }
+        Err(e) => tracing::warn!("failed to query opencode parts: {e}"),
+    }
+
+    messages
+}
+
+fn parse_opencode_transcript_part(id: &str, data: &str) -> Vec<TranscriptBlock> {
+    let Ok(part) = serde_json::from_str::<serde_json::Value>(data) else {
+        return Vec::new();
+    };
+    let text = || {
+        part.get("text")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    match part.get("type").and_then(|v| v.as_str()) {
+        Some("text") => vec![TranscriptBlock::Text { text: text() }],
+        Some("reasoning") => vec![TranscriptBlock::Reasoning { text: text() }],
+        Some("tool") => {
+            let tool = part
+                .get("tool")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            let status = part.pointer("/state/status").and_then(|v| v.as_str());
+            let mut blocks = vec![TranscriptBlock::ToolUse {
+                id: id.to_string(),
+                tool,
+            }];
+            if matches!(status, Some("completed") | Some("error")) {
+                blocks.push(TranscriptBlock::ToolResult {
+                    tool_use_id: id.to_string(),
+                    is_error: status == Some("error"),
+                });
+            }
+            blocks
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenCodeMessage {
+    tokens: Option<OpenCodeTokens>,
+    #[serde(default)]
+    cost: f64,
+}
+
+#[derive(Deserialize)]
+struct OpenCodeTokens {
+    #[serde(default)]
+    input: u64,
+    #[serde(default)]
+    output: u64,
+    #[serde(default)]
+    reasoning: u64,
+    cache: Option<OpenCodeCacheTokens>,
+}
+
+#[derive(Deserialize)]
+struct OpenCodeCacheTokens {
+    #[serde(default)]
+    read: u64,
+    #[serde(default)]
+    write: u64,
+}
+
+fn parse_opencode_message_usage(data: &str) -> Option<TokenUsage> {
+    let msg: OpenCodeMessage = serde_json::from_str(data).ok()?;
+    let tokens = msg.tokens?;
+    let (cache_read, cache_write) = tokens.cache.map(|c| (c.read, c.write)).unwrap_or_default();
+    Some(TokenUsage {
+        input_tokens: tokens.input,
+        output_tokens: tokens.output + tokens.reasoning,
+        cache_read_tokens: cache_read,
+        cache_write_tokens: cache_write,
+        cost_usd: msg.cost,
+    })
+}
+
+#[derive(Deserialize)]
+struct OpenCodePart {
+    tool: Option<String>,
+    state: Option<OpenCodeToolState>,
+}
+
+#[derive(Deserialize)]
+struct OpenCodeToolState {
+    status: String,
+    input: Option<serde_json::Value>,
+    title: Option<String>,
+    output: Option<serde_json::Value>,
+    time: Option<OpenCodeToolTime>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenCodeToolTime {
+    start: Option<u64>,
+    end: Option<u64>,
+}
+
+fn parse_opencode_part(id: &str, data: &str, time_created: u64) -> Option<ToolCall> {
+    let part: OpenCodePart = serde_json::from_str(data).ok()?;
+    let tool = part.tool?;
+    let state = part.state?;
+    let input = state
+        .input
+        .as_ref()
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+    let summary = summarize_input(&tool, &input);
+
+    let duration_ms = state.time.as_ref().and_then(|t| {
+        let start = t.start?;
+        let end = t.end?;
+        Some(end.saturating_sub(start))
+    });
+
+    let timestamp = state
+        .time
+        .as_ref()
+        .and_then(|t| t.start)
+        .unwrap_or(time_created);
+
+    let title = if state.status == "error" {
+        state.error.or(state.title)
+    } else {
+        state.title
+    };
+
+    Some(ToolCall {
+        id: id.to_string(),
+        tool,
+        title,
+        input_summary: summary,
+        status: state.status,
+        timestamp,
+        duration_ms,
+        input_text: input_text(&input),
+    })
+}
+
+fn parse_opencode_part_detail(id: &str, data: &str, time_created: u64) -> Option<ToolCallDetail> {
+    let part: OpenCodePart = serde_json::from_str(data).ok()?;
+    let tool = part.tool?;
+    let state = part.state?;
+
+    let duration_ms = state.time.as_ref().and_then(|t| {
+        let start = t.start?;
+        let end = t.end?;
+        Some(end.saturating_sub(start))
+    });
+    let timestamp = state
+        .time
+        .as_ref()
+        .and_then(|t| t.start)
+        .unwrap_or(time_created);
+
+    let (output, mut output_truncated) = match state.output {
+        Some(serde_json::Value::String(s)) => {
+            let (s, truncated) = truncate_output(s);
+            (Some(s), truncated)
+        }
+        Some(serde_json::Value::Null) | None => (None, false),
+        Some(other) => {
+            let (s, truncated) = truncate_output(other.to_string());
+            (Some(s), truncated)
+        }
+    };
+
+    let error = state.error.map(|e| {
+        let (e, truncated) = truncate_output(e);
+        output_truncated |= truncated;
+        e
+    });
+
+    Some(ToolCallDetail {
+        id: id.to_string(),
+        tool,
+        title: state.title,
+        input: state.input.unwrap_or(serde_json::Value::Null),
+        output,
+        output_truncated,
+        error,
+        status: state.status,
+        timestamp,
+        duration_ms,
+    })
+}
+
+pub fn opencode_db_path() -> Option<std::path::PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    let path = if cfg!(target_os = "macos") {
+        std::path::PathBuf::from(&home).join("Library/Application Support/opencode/opencode.db")
+    } else {
+        let xdg_data =
+            std::env::var("XDG_DATA_HOME").unwrap_or_else(|_| format!("{home}/.local/share"));
+        std::path::PathBuf::from(xdg_data).join("opencode/opencode.db")
+    };
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_opencode_tool_part() {
+        let data = r#"{"type":"tool","tool":"edit","callID":"call_1","state":{"status":"completed","input":{"file_path":"/src/main.rs"},"output":"ok","title":"Edit main.rs","time":{"start":1000,"end":1250}}}"#;
+        let call = parse_opencode_part("prt_1", data, 1000).unwrap();
+        assert_eq!(call.id, "prt_1");
+        assert_eq!(call.tool, "edit");
+        assert_eq!(call.title, Some("Edit main.rs".to_string()));
+        assert_eq!(call.input_summary, "/src/main.rs");
+        assert_eq!(call.status, "completed");
+        assert_eq!(call.duration_ms, Some(250));
+    }
+
+    #[test]
+    fn should_parse_opencode_tool_part_detail() {
+        let data = r#"{"type":"tool","tool":"bash","callID":"call_1","state":{"status":"completed","input":{"command":"ls -la"},"output":"total 0","title":"ls","time":{"start":1000,"end":1250}}}"#;
+        let detail = parse_opencode_part_detail("prt_1", data, 1000).unwrap();
+        assert_eq!(detail.id, "prt_1");
+        assert_eq!(detail.input["command"], "ls -la");
+        assert_eq!(detail.output.as_deref(), Some("total 0"));
+        assert!(!detail.output_truncated);
+        assert_eq!(detail.duration_ms, Some(250));
+    }
+
+    #[test]
+    fn should_truncate_opencode_tool_errors() {
+        let stderr = "e".repeat(crate::toolcalls::MAX_TOOL_OUTPUT_BYTES + 10);
+        let data = serde_json::json!({"type":"tool","tool":"bash","state":{"status":"error","input":{},"error":stderr}});
+        let detail = parse_opencode_part_detail("prt_1", &data.to_string(), 1000).unwrap();
+        assert_eq!(
+            detail.error.map(|e| e.len()),
+            Some(crate::toolcalls::MAX_TOOL_OUTPUT_BYTES)
+        );
+        assert!(detail.output_truncated);
+    }
+
+    #[test]
+    fn should_parse_opencode_message_usage() {
+        let data = r#"{"role":"assistant","modelID":"gpt-5","cost":0.0123,"tokens":{"input":100,"output":40,"reasoning":10,"cache":{"read":300,"write":20}}}"#;
+        let usage = parse_opencode_message_usage(data).unwrap();
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.output_tokens, 50);
+        assert_eq!(usage.cache_read_tokens, 300);
+        assert_eq!(usage.cache_write_tokens, 20);
+        assert!((usage.cost_usd - 0.0123).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_parse_opencode_tool_part_into_use_and_result() {
+        let data =
+            r#"{"type":"tool","tool":"bash","callID":"call_1","state":{"status":"completed"}}"#;
+        let blocks = parse_opencode_transcript_part("prt_1", data);
+        assert_eq!(blocks.len(), 2);
+        assert!(
+            matches!(&blocks[0], TranscriptBlock::ToolUse { id, tool } if id == "prt_1" && tool == "bash")
+        );
+        assert!(matches!(
+            &blocks[1],
+            TranscriptBlock::ToolResult {
+                is_error: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn should_supersede_session_when_config_id_changes() {
+        let member = |opencode_session_id| MemberInfo {
+            team_name: "alpha",
+            name: "implementer",
+            agent_id: "",
+            cwd: "/work/app",
+            spawned_at: 0,
+            tmux_pane_id: None,
+            opencode_session_id,
+        };
+        let session = SessionRef::Id("ses_1".into());
+        assert!(!OpenCodeBackend.session_superseded(&member(Some("ses_1")), &session));
+        assert!(OpenCodeBackend.session_superseded(&member(Some("ses_2")), &session));
+        assert!(OpenCodeBackend.session_superseded(&member(None), &session));
+    }
+
+    #[test]
+    fn should_query_only_tools_updated_since_high_water() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE part (id TEXT, session_id TEXT, time_created INTEGER, time_updated INTEGER, data TEXT);",
+        )
+        .unwrap();
+        let tool = |status: &str| {
+            format!(
+                r#"{{"type":"tool","tool":"bash","state":{{"status":"{status}","input":{{"command":"ls"}}}}}}"#
+            )
+        };
+        let insert = |id: &str, created: u64, updated: u64, data: &str| {
+            conn.execute(
+                "INSERT INTO part VALUES (?1, 'ses_1', ?2, ?3, ?4)",
+                rusqlite::params![id, created, updated, data],
+            )
+            .unwrap();
+        };
+        insert("prt_1", 100, 150, &tool("completed"));
+        insert("prt_2", 200, 200, &tool("running"));
+        insert("prt_3", 120, 400, &tool("error"));
+
+        let (calls, high_water) = query_tools_since(&conn, "ses_1", 0).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(high_water, 400);
+
+        let (calls, high_water) = query_tools_since(&conn, "ses_1", 151).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "prt_3");
+        assert_eq!(high_water, 400);
+
+        // A part finishing in the high-water millisecond isn't lost.
+        insert("prt_4", 300, 400, &tool("completed"));
+        let (calls, high_water) = query_tools_since(&conn, "ses_1", 400).unwrap();
+        let ids: Vec<&str> = calls.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["prt_3", "prt_4"]);
+        assert_eq!(high_water, 400);
+    }
+}
@@ -0,0 +1,478 @@
+//! Opt-in capture of proxied opencode traffic for debugging, enabled by `debugCapture`
+//! in `config.json`. Each exchange is written to `~/.nightshift/captures/<id>.json`, and
+//! the oldest files are dropped once `maxEntries` is exceeded.
+//!
+//! Bodies are kept up to `maxBodyBytes`. A response that runs past the cap (typically an
+//! SSE stream) is saved at that point and streams on uncaptured. Upgraded websocket
+//! traffic is never captured, only the handshake.
+//!
+//! Files are written owner-only by a background thread so proxied responses never wait on
+//! the disk; when it falls behind, new captures are dropped rather than queued.
+
+use base64::Engine;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    crate::auth::SIGNATURE_HEADER,
+];
+
+/// Finished captures waiting for the writer thread.
+const WRITE_QUEUE: usize = 64;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureConfig {
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+impl CaptureConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_entries == 0 {
+            anyhow::bail!("debugCapture.maxEntries must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+fn default_max_entries() -> usize {
+    200
+}
+
+fn default_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSummary {
+    pub id: u64,
+    pub at: u64,
+    pub method: String,
+    pub path: String,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub upgrade: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedMessage {
+    /// Repeated headers are joined with `, `; credentials are redacted.
+    pub headers: BTreeMap<String, String>,
+    /// Set when the captured bytes are valid UTF-8.
+    pub body: Option<String>,
+    pub body_base64: Option<String>,
+    /// Bytes seen, which may exceed what was kept.
+    pub size: u64,
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Capture {
+    #[serde(flatten)]
+    pub summary: CaptureSummary,
+    pub request: CapturedMessage,
+    pub response: CapturedMessage,
+}
+
+pub fn capture_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".nightshift").join("captures")
+}
+
+enum WriterJob {
+    Save(Box<Capture>),
+    Flush(mpsc::Sender<()>),
+}
+
+pub struct CaptureStore {
+    dir: PathBuf,
+    config: CaptureConfig,
+    next_id: AtomicU64,
+    writer: SyncSender<WriterJob>,
+}
+
+impl CaptureStore {
+    pub fn open(dir: PathBuf, config: CaptureConfig) -> Self {
+        if let Err(e) = create_private_dir(&dir) {
+            tracing::warn!("failed to create capture dir {}: {e}", dir.display());
+        }
+        let (writer, jobs) = mpsc::sync_channel(WRITE_QUEUE);
+        {
+            let dir = dir.clone();
+            let max_entries = config.max_entries;
+            std::thread::Builder::new()
+                .name("capture-writer".into())
+                .spawn(move || run_writer(&dir, max_entries, jobs))
+                .expect("failed to spawn capture writer");
+        }
+        let next_id = capture_ids(&dir).last().map(|id| id + 1).unwrap_or(1);
+        tracing::info!(
+            "capturing proxied traffic to {} (last {} exchanges)",
+            dir.display(),
+            config.max_entries
+        );
+        Self {
+            dir,
+            config,
+            next_id: AtomicU64::new(next_id),
+            writer,
+        }
+    }
+
+    pub fn begin(self: &Arc<Self>, parts: &axum::http::request::Parts) -> PendingCapture {
+        PendingCapture {
+            store: Arc::clone(self),
+            started: Instant::now(),
+            summary: CaptureSummary {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                at: crate::teams::now_ms(),
+                method: parts.method.to_string(),
+                path: parts
+                    .uri
+                    .path_and_query()
+                    .map(|pq| pq.to_string())
+                    .unwrap_or_default(),
+                status: None,
+                duration_ms: 0,
+                upgrade: false,
+            },
+            request_headers: redact(&parts.headers),
+            request_body: BodyTap::shared(self.config.max_body_bytes),
+            response_headers: BTreeMap::new(),
+            response_body: BodyTap::new(self.config.max_body_bytes),
+        }
+    }
+
+    /// Newest first.
+    pub fn list(&self, limit: usize) -> Vec<CaptureSummary> {
+        capture_ids(&self.dir)
+            .into_iter()
+            .rev()
+            .take(limit)
+            .filter_map(|id| self.get(id))
+            .map(|c| c.summary)
+            .collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<Capture> {
+        let content = std::fs::read_to_string(capture_path(&self.dir, id)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Blocks until every capture queued so far is on disk.
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.writer.send(WriterJob::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    fn save(&self, capture: Capture) {
+        match self.writer.try_send(WriterJob::Save(Box::new(capture))) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("capture writer is behind, dropping a capture");
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::warn!("capture writer is gone, dropping a capture");
+            }
+        }
+    }
+}
+
+fn run_writer(dir: &Path, max_entries: usize, jobs: Receiver<WriterJob>) {
+    for job in jobs {
+        match job {
+            WriterJob::Save(capture) => write_capture(dir, max_entries, &capture),
+            WriterJob::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+fn write_capture(dir: &Path, max_entries: usize, capture: &Capture) {
+    let json = match serde_json::to_vec_pretty(capture) {
+        Ok(json) => json,
+        Err(e) => {
+            tracing::warn!("failed to serialize capture: {e}");
+            return;
+        }
+    };
+    if let Err(e) = write_private(&capture_path(dir, capture.summary.id), &json) {
+        tracing::warn!("failed to write capture: {e}");
+        return;
+    }
+    let ids = capture_ids(dir);
+    let excess = ids.len().saturating_sub(max_entries);
+    for id in &ids[..excess] {
+        let _ = std::fs::remove_file(capture_path(dir, *id));
+    }
+}
+
+/// Captures hold prompts and file contents, so the directory is owner-only.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+    }
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(dir)
+}
+
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+fn capture_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:010}.json"))
+}
+
+/// Ascending.
+fn capture_ids(dir: &Path) -> Vec<u64> {
+    let mut ids: Vec<u64> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json")?.parse().ok())
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn redact(headers: &axum::http::HeaderMap) -> BTreeMap<String, String> {
+    let mut out: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+            "[redacted]".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        out.entry(name.as_str().to_string())
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    out
+}
+
+struct BodyTap {
+    kept: Vec<u8>,
+    limit: usize,
+    size: u64,
+}
+
+impl BodyTap {
+    fn new(limit: usize) -> Self {
+        Self {
+            kept: Vec::new(),
+            limit,
+            size: 0,
+        }
+    }
+
+    fn shared(limit: usize) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self::new(limit)))
+    }
+
+    fn push(&mut self, chunk: &[u8]) {
+        self.size += chunk.len() as u64;
+        let room = self.limit.saturating_sub(self.kept.len());
+        self.kept.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+
+    fn full(&self) -> bool {
+        self.size > self.limit as u64
+    }
+
+    fn message(&self, headers: BTreeMap<String, String>) -> CapturedMessage {
+        let (body, body_base64) = match std::str::from_utf8(&self.kept) {
+            Ok(_) if self.kept.is_empty() => (None, None),
+            Ok(text) => (Some(text.to_string()), None),
+            Err(_) => (
+                None,
+                Some(base64::engine::general_purpose::STANDARD.encode(&self.kept)),
+            ),
+        };
+        CapturedMessage {
+            headers,
+            body,
+            body_base64,
+            size: self.size,
+            truncated: self.full(),
+        }
+    }
+}
+
+/// One exchange in flight. Saved by [`PendingCapture::finish`], at the latest when the
+/// proxy's access log for the response is dropped.
+pub struct PendingCapture {
+    store: Arc<CaptureStore>,
+    started: Instant,
+    summary: CaptureSummary,
+    request_headers: BTreeMap<String, String>,
+    request_body: Arc<Mutex<BodyTap>>,
+    response_headers: BTreeMap<String, String>,
+    response_body: BodyTap,
+}
+
+impl PendingCapture {
+    /// Tees the request body into the capture as the upstream consumes it.
+    pub fn tap_request(&self, body: axum::body::Body) -> axum::body::Body {
+        use futures_util::StreamExt;
+        let tap = Arc::clone(&self.request_body);
+        axum::body::Body::from_stream(body.into_data_stream().map(move |chunk| {
+            if let Ok(ref bytes) = chunk {
+                tap.lock().unwrap_or_else(|e| e.into_inner()).push(bytes);
+            }
+            chunk
+        }))
+    }
+
+    pub fn set_response(&mut self, status: u16, headers: &axum::http::HeaderMap, upgrade: bool) {
+        self.summary.status = Some(status);
+        self.summary.upgrade = upgrade;
+        self.response_headers = redact(headers);
+    }
+
+    /// Returns true once the response body passed the cap and the capture should be
+    /// finished early.
+    pub fn push_response(&mut self, chunk: &Bytes) -> bool {
+        self.response_body.push(chunk);
+        self.response_body.full()
+    }
+
+    pub fn finish(self) {
+        let mut summary = self.summary;
+        summary.duration_ms = self.started.elapsed().as_millis() as u64;
+        let request = self
+            .request_body
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .message(self.request_headers);
+        let capture = Capture {
+            summary,
+            request,
+            response: self.response_body.message(self.response_headers),
+        };
+        self.store.save(capture);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(dir: &std::path::Path, max_entries: usize) -> Arc<CaptureStore> {
+        Arc::new(CaptureStore::open(
+            dir.to_path_buf(),
+            CaptureConfig {
+                max_entries,
+                max_body_bytes: 8,
+            },
+        ))
+    }
+
+    fn parts(path: &str) -> axum::http::request::Parts {
+        axum::http::Request::post(path)
+            .header("authorization", "Bearer secret")
+            .header("accept", "text/plain")
+            .header("accept", "application/json")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[test]
+    fn should_redact_credentials_and_join_repeated_headers() {
+        let headers = redact(&parts("/").headers);
+        assert_eq!(headers["authorization"], "[redacted]");
+        assert_eq!(headers["accept"], "text/plain, application/json");
+    }
+
+    #[test]
+    fn should_cap_bodies_and_flag_truncation() {
+        let mut tap = BodyTap::new(8);
+        tap.push(b"hello ");
+        assert!(!tap.full());
+        tap.push(b"world");
+        assert!(tap.full());
+        let msg = tap.message(BTreeMap::new());
+        assert_eq!(msg.body.as_deref(), Some("hello wo"));
+        assert_eq!(msg.size, 11);
+        assert!(msg.truncated);
+
+        let mut binary = BodyTap::new(8);
+        binary.push(&[0xff, 0xfe]);
+        assert_eq!(
+            binary.message(BTreeMap::new()).body_base64.as_deref(),
+            Some("//4=")
+        );
+    }
+
+    #[test]
+    fn should_keep_only_the_newest_captures() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), 2);
+        for path in ["/a", "/b", "/c"] {
+            let mut pending = store.begin(&parts(path));
+            pending.set_response(200, &axum::http::HeaderMap::new(), false);
+            pending.push_response(&Bytes::from_static(b"ok"));
+            pending.finish();
+        }
+        store.flush();
+
+        let listed = store.list(10);
+        let paths: Vec<&str> = listed.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/c", "/b"]);
+
+        let capture = store.get(listed[0].id).unwrap();
+        assert_eq!(capture.summary.status, Some(200));
+        assert_eq!(capture.response.body.as_deref(), Some("ok"));
+        assert_eq!(capture.request.headers["authorization"], "[redacted]");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(dir.path()), 0o700);
+            assert_eq!(mode(&capture_path(dir.path(), listed[0].id)), 0o600);
+        }
+
+        let reopened = self::store(dir.path(), 2);
+        assert_eq!(reopened.begin(&parts("/d")).summary.id, listed[0].id + 1);
+    }
+}
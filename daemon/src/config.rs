@@ -1,3 +1,10 @@
+use crate::archive::RetentionPolicy;
+use crate::auth::AuthConfig;
+use crate::capture::CaptureConfig;
+use crate::policy::AccessPolicy;
+use crate::ratelimit::RateLimitConfig;
+use crate::tls::TlsConfig;
+use anyhow::Context;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
@@ -9,6 +16,47 @@ pub struct Config {
     pub server_url: String,
     pub public_url: String,
     pub proxy_port: u16,
+    /// Interface the proxy's TCP listener binds to. Defaults to all interfaces,
+    /// unless `unixSocket` is set, in which case TCP is only opened if this is given.
+    pub bind_address: Option<String>,
+    /// Unix domain socket for local CLI/agent traffic.
+    pub unix_socket: Option<PathBuf>,
+    /// Serve the TCP listener over TLS.
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub archive_retention: RetentionPolicy,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Record proxied opencode traffic for `GET /debug/captures`.
+    pub debug_capture: Option<CaptureConfig>,
+    #[serde(default)]
+    pub access_policy: AccessPolicy,
+    /// Throttle requests proxied to opencode; unlimited when absent.
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0";
+
+impl Config {
+    /// Settings that parse but can't be served safely.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.auth.validate()?;
+        if let Some(ref rate_limit) = self.rate_limit {
+            rate_limit.validate()?;
+        }
+        if let Some(ref capture) = self.debug_capture {
+            capture.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn tcp_bind_address(&self) -> Option<&str> {
+        match (&self.bind_address, &self.unix_socket) {
+            (Some(addr), _) => Some(addr),
+            (None, Some(_)) => None,
+            (None, None) => Some(DEFAULT_BIND_ADDRESS),
+        }
+    }
 }
 
 fn config_path() -> PathBuf {
@@ -16,30 +64,49 @@ fn config_path() -> PathBuf {
     PathBuf::from(home).join(".nightshift").join("config.json")
 }
 
-fn load_from(path: &Path) -> Option<Config> {
-    let content = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&content).ok()
+/// `Ok(None)` only when the file doesn't exist; unreadable or malformed files are errors.
+fn load_from(path: &Path) -> anyhow::Result<Option<Config>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let cfg = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(cfg))
 }
 
-pub fn load() -> Option<Config> {
-    let cfg = load_from(&config_path());
-    if cfg.is_some() {
-        return cfg;
+/// Errors if the config file is present but invalid: falling back to defaults
+/// could start the proxy without the auth it was meant to have.
+pub fn load() -> anyhow::Result<Option<Config>> {
+    let path = config_path();
+    if let Some(cfg) = load_from(&path)? {
+        cfg.validate()
+            .map_err(|e| anyhow::anyhow!("invalid {}: {e}", path.display()))?;
+        return Ok(Some(cfg));
     }
 
     #[cfg(debug_assertions)]
     {
         tracing::info!("no config file found, using dev defaults");
-        Some(Config {
+        Ok(Some(Config {
             version: 1,
             server_url: "http://localhost:4001".into(),
             public_url: "http://localhost:19277".into(),
             proxy_port: 19277,
-        })
+            bind_address: None,
+            unix_socket: None,
+            tls: None,
+            archive_retention: RetentionPolicy::default(),
+            auth: AuthConfig::default(),
+            debug_capture: None,
+            access_policy: AccessPolicy::default(),
+            rate_limit: None,
+        }))
     }
 
     #[cfg(not(debug_assertions))]
-    None
+    Ok(None)
 }
 
 #[cfg(test)]
@@ -57,33 +124,108 @@ mod tests {
         )
         .unwrap();
 
-        let cfg = load_from(&path).expect("should parse");
+        let cfg = load_from(&path).unwrap().expect("should parse");
         assert_eq!(cfg.version, 1);
         assert_eq!(cfg.server_url, "https://nightshift.fly.dev");
         assert_eq!(cfg.public_url, "https://sprite-abc.fly.dev:8080");
         assert_eq!(cfg.proxy_port, 8080);
     }
 
+    #[test]
+    fn should_keep_tcp_off_when_only_unix_socket_is_configured() {
+        let parse = |extra: &str| -> Config {
+            serde_json::from_str(&format!(
+                r#"{{"version":1,"serverUrl":"s","publicUrl":"p","proxyPort":8080{extra}}}"#
+            ))
+            .unwrap()
+        };
+        assert_eq!(parse("").tcp_bind_address(), Some("0.0.0.0"));
+        assert_eq!(
+            parse(r#","bindAddress":"127.0.0.1""#).tcp_bind_address(),
+            Some("127.0.0.1")
+        );
+
+        let socket_only = parse(r#","unixSocket":"/tmp/nightshift.sock""#);
+        assert_eq!(socket_only.tcp_bind_address(), None);
+        assert_eq!(
+            socket_only.unix_socket.as_deref(),
+            Some(Path::new("/tmp/nightshift.sock"))
+        );
+        assert_eq!(
+            parse(r#","unixSocket":"/tmp/n.sock","bindAddress":"::1""#).tcp_bind_address(),
+            Some("::1")
+        );
+    }
+
+    #[test]
+    fn should_reject_weak_auth_settings() {
+        let parse = |auth: &str| -> Config {
+            serde_json::from_str(&format!(
+                r#"{{"version":1,"serverUrl":"s","publicUrl":"p","proxyPort":8080,"auth":{auth}}}"#
+            ))
+            .unwrap()
+        };
+        assert!(parse(r#"{"tokens":["t"]}"#).validate().is_ok());
+        assert!(parse(r#"{"tokens":[""]}"#).validate().is_err());
+        assert!(parse(r#"{"hmacSecret":"short"}"#).validate().is_err());
+    }
+
+    #[test]
+    fn should_reject_rate_limits_that_lock_everyone_out() {
+        let parse = |limit: &str| -> Config {
+            serde_json::from_str(&format!(
+                r#"{{"version":1,"serverUrl":"s","publicUrl":"p","proxyPort":8080,"rateLimit":{limit}}}"#
+            ))
+            .unwrap()
+        };
+        assert!(parse("{}").validate().is_ok());
+        assert!(parse(r#"{"perClientBurst":0.5}"#).validate().is_err());
+        assert!(parse(r#"{"globalRps":0}"#).validate().is_err());
+    }
+
+    #[test]
+    fn should_reject_an_empty_capture_ring() {
+        let parse = |capture: &str| -> Config {
+            serde_json::from_str(&format!(
+                r#"{{"version":1,"serverUrl":"s","publicUrl":"p","proxyPort":8080,"debugCapture":{capture}}}"#
+            ))
+            .unwrap()
+        };
+        assert!(parse("{}").validate().is_ok());
+        assert!(parse(r#"{"maxEntries":0}"#).validate().is_err());
+    }
+
     #[test]
     fn should_return_none_when_file_absent() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("nonexistent.json");
-        assert!(load_from(&path).is_none());
+        assert!(load_from(&path).unwrap().is_none());
     }
 
     #[test]
-    fn should_return_none_when_json_malformed() {
+    fn should_fail_when_json_malformed() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("config.json");
         fs::write(&path, "not json").unwrap();
-        assert!(load_from(&path).is_none());
+        assert!(load_from(&path).is_err());
     }
 
     #[test]
-    fn should_return_none_when_required_fields_missing() {
+    fn should_fail_when_required_fields_missing() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("config.json");
         fs::write(&path, r#"{"serverUrl":"https://example.com"}"#).unwrap();
-        assert!(load_from(&path).is_none());
+        assert!(load_from(&path).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_fail_when_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory where the file should be: reading it fails with something other
+        // than NotFound.
+        let path = dir.path().join("config.json");
+        fs::create_dir(&path).unwrap();
+        assert!(load_from(&path).is_err());
     }
 }
@@ -2,8 +2,9 @@ use anyhow::Result;
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 use std::time::Duration;
-use tokio::signal;
 
+use crate::health::HEALTH;
+use crate::shutdown::Shutdown;
 use crate::update;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -22,6 +23,11 @@ const WATCHDOG_SLEEP: Duration = Duration::from_secs(5);
 const WATCHDOG_THRESHOLD: Duration = Duration::from_secs(5);
 const OPENCODE_PID_FILE: &str = "opencode.pid";
 const READINESS_TIMEOUT: Duration = Duration::from_secs(8);
+const OPENCODE_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+const DEREGISTER_TIMEOUT: Duration = Duration::from_secs(3);
+/// The final team flush rescans and reindexes everything; cap it so a slow disk
+/// cannot hold shutdown past the service manager's stop timeout.
+const TEAMS_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[cfg(unix)]
 fn kill_stale_opencode(pid_path: &std::path::Path) {
@@ -45,17 +51,44 @@ fn kill_stale_opencode(pid_path: &std::path::Path) {
     let _ = std::fs::remove_file(pid_path);
 }
 
+/// SIGTERM, then SIGKILL if opencode is still running after `OPENCODE_STOP_TIMEOUT`.
+async fn stop_opencode(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        unsafe {
+            libc::kill(pid as i32, libc::SIGTERM);
+        }
+        match tokio::time::timeout(OPENCODE_STOP_TIMEOUT, child.wait()).await {
+            Ok(status) => {
+                tracing::info!("opencode stopped: {:?}", status);
+                return;
+            }
+            Err(_) => tracing::warn!(
+                "opencode still running {:?} after SIGTERM, killing",
+                OPENCODE_STOP_TIMEOUT
+            ),
+        }
+    }
+    child.kill().await.ok();
+}
+
+/// Waits until opencode answers HTTP, not just until its port accepts connections:
+/// Bun binds the listener before the server is ready to handle requests.
 async fn wait_for_opencode(port: u16, timeout: Duration) -> Result<()> {
     let deadline = tokio::time::Instant::now() + timeout;
     loop {
-        if tokio::net::TcpStream::connect(("127.0.0.1", port))
-            .await
-            .is_ok()
-        {
+        let status = crate::health::probe_opencode(port).await;
+        if status.answering {
             return Ok(());
         }
         if tokio::time::Instant::now() >= deadline {
-            anyhow::bail!("opencode did not become ready within {:?}", timeout);
+            anyhow::bail!(
+                "opencode did not become ready within {:?}: {}",
+                timeout,
+                status
+                    .error
+                    .unwrap_or_else(|| format!("status {:?}", status.status))
+            );
         }
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
@@ -129,6 +162,10 @@ fn restart_self_for_thaw(child_pid: Option<i32>) -> ! {
         .args(&args)
         .env_remove("NIGHTSHIFT_TEST_FORCE_THAW") // prevent infinite restart loop in tests
         .env("NIGHTSHIFT_TEST_IS_RESTART", "1")
+        .env(
+            crate::health::GENERATION_ENV,
+            (crate::health::watchdog_generation() + 1).to_string(),
+        )
         .exec();
     eprintln!("nightshift: execve failed: {err}");
     unsafe { libc::_exit(1) }
@@ -184,7 +221,12 @@ fn spawn_watchdog(child_pid: Option<i32>) {
 fn spawn_watchdog(_child_pid: Option<i32>) {}
 
 pub async fn run() -> Result<()> {
-    tracing::info!("starting nightshift daemon v{}", env!("CARGO_PKG_VERSION"));
+    tracing::info!(
+        "starting nightshift daemon v{} (watchdog generation {})",
+        env!("CARGO_PKG_VERSION"),
+        crate::health::watchdog_generation()
+    );
+    HEALTH.mark_started(update::is_enabled());
 
     if update::is_enabled() {
         match update::check_and_apply().await {
@@ -227,7 +269,7 @@ pub async fn run() -> Result<()> {
         });
     }
 
-    let cfg = crate::config::load();
+    let cfg = crate::config::load()?;
 
     let proxy_port = cfg.as_ref().map(|c| c.proxy_port).unwrap_or(PROXY_PORT);
     let url_override = cfg.as_ref().map(|c| c.public_url.as_str());
@@ -258,7 +300,33 @@ pub async fn run() -> Result<()> {
     // NOTE(victor): No supervisor. If opencode dies, the daemon exits.
     // The service manager (launchd/systemd/sprites supervisor) restarts the daemon,
     // which spawns a fresh opencode. This gives us fresh FDs and a clean epoll state.
-    let mut child = tokio::process::Command::new("opencode")
+    let mut command = tokio::process::Command::new("opencode");
+    // Own process group, so a terminal Ctrl-C reaches only the daemon
+    // and opencode keeps serving until the proxy has drained. The flip side is that
+    // a SIGKILLed or crashed daemon would leave opencode running: on Linux the kernel
+    // sends it SIGTERM instead, elsewhere the next start reaps it via the pid file.
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(target_os = "linux")]
+    {
+        let parent = std::process::id() as libc::pid_t;
+        // SAFETY: only async-signal-safe libc calls run between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                // The death signal follows the spawning thread; `run` stays on the
+                // main thread, which lives as long as the daemon.
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                // The daemon may have died before the signal was armed.
+                if libc::getppid() != parent {
+                    return Err(std::io::Error::from_raw_os_error(libc::ESRCH));
+                }
+                Ok(())
+            });
+        }
+    }
+    let mut child = command
         .args([
             "serve",
             "--log-level",
@@ -285,7 +353,25 @@ pub async fn run() -> Result<()> {
     wait_for_opencode(OPENCODE_PORT, READINESS_TIMEOUT).await?;
     tracing::info!("opencode ready on port {}", OPENCODE_PORT);
 
-    let (node_id, node) = crate::nodes::register(proxy_port, url_override)?;
+    let tls = match cfg.as_ref().and_then(|c| c.tls.as_ref().map(|t| (c, t))) {
+        Some((c, tls)) => {
+            let hosts: Vec<String> = reqwest::Url::parse(&c.public_url)
+                .ok()
+                .and_then(|u| u.host_str().map(|h| h.trim_matches(['[', ']']).to_string()))
+                .into_iter()
+                .collect();
+            let material = crate::tls::load_or_generate(tls, &hosts)?;
+            tracing::info!("proxy tls certificate sha256 {}", material.fingerprint);
+            Some(material)
+        }
+        None => None,
+    };
+
+    let (node_id, node) = crate::nodes::register(
+        proxy_port,
+        url_override,
+        tls.as_ref().map(|t| t.fingerprint.clone()),
+    )?;
 
     let server_url = cfg.as_ref().map(|c| c.server_url.clone());
 
@@ -300,7 +386,10 @@ pub async fn run() -> Result<()> {
             ];
             for (attempt, delay) in delays.iter().enumerate() {
                 match crate::nodes::register_remote(&reg_url, &reg_node).await {
-                    Ok(()) => return,
+                    Ok(()) => {
+                        HEALTH.record_heartbeat();
+                        return;
+                    }
                     Err(e) => {
                         tracing::warn!(
                             "remote registration attempt {}/{} failed: {e}",
@@ -327,13 +416,12 @@ pub async fn run() -> Result<()> {
             loop {
                 interval.tick().await;
                 match crate::nodes::heartbeat_remote(&heartbeat_url, &heartbeat_id).await {
-                    Ok(crate::nodes::HeartbeatResult::Ok) => {}
+                    Ok(crate::nodes::HeartbeatResult::Ok) => HEALTH.record_heartbeat(),
                     Ok(crate::nodes::HeartbeatResult::NodeExpired) => {
                         tracing::warn!("node expired from server, re-registering");
-                        if let Err(e) =
-                            crate::nodes::register_remote(&heartbeat_url, &heartbeat_node).await
-                        {
-                            tracing::warn!("re-registration failed: {e}");
+                        match crate::nodes::register_remote(&heartbeat_url, &heartbeat_node).await {
+                            Ok(()) => HEALTH.record_heartbeat(),
+                            Err(e) => tracing::warn!("re-registration failed: {e}"),
                         }
                     }
                     Err(e) => {
@@ -348,11 +436,42 @@ pub async fn run() -> Result<()> {
     // also stop firing. OS thread uses nanosleep/futex, which resumes regardless.
     spawn_watchdog(child_pid);
 
-    let teams_handle = crate::teams::new_handle();
+    let retention = cfg
+        .as_ref()
+        .map(|c| c.archive_retention.clone())
+        .unwrap_or_default();
+    let teams_handle = crate::teams::new_handle(retention);
+    let options = crate::proxy::ProxyOptions {
+        auth: cfg.as_ref().map(|c| c.auth.clone()).unwrap_or_default(),
+        capture: cfg.as_ref().and_then(|c| c.debug_capture.clone()),
+        policy: cfg
+            .as_ref()
+            .map(|c| c.access_policy.clone())
+            .unwrap_or_default(),
+        rate_limit: cfg.as_ref().and_then(|c| c.rate_limit.clone()),
+    };
+    let listen = crate::proxy::Listen {
+        bind_address: match cfg.as_ref() {
+            Some(c) => c.tcp_bind_address().map(str::to_string),
+            None => Some("0.0.0.0".into()),
+        },
+        port: proxy_port,
+        unix_socket: cfg.as_ref().and_then(|c| c.unix_socket.clone()),
+        tls: tls.map(|t| t.server_config),
+    };
     tokio::spawn(crate::teams::spawn_watcher(teams_handle.clone()));
 
     let start_time = std::time::Instant::now();
 
+    let shutdown = Shutdown::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            crate::shutdown::signal().await;
+            shutdown.trigger();
+        }
+    });
+
     tokio::select! {
         status = child.wait() => {
             #[cfg(unix)]
@@ -361,25 +480,43 @@ pub async fn run() -> Result<()> {
                 tracing::info!("opencode exited during planned restart, waiting for execve");
                 loop { std::thread::sleep(std::time::Duration::from_secs(60)); }
             }
-            tracing::error!("opencode exited: {:?}, daemon will exit", status);
-            std::process::exit(1);
-        }
-        result = crate::proxy::serve(OPENCODE_PORT, proxy_port, data_dir.to_string_lossy().into_owned(), start_time, teams_handle.clone()) => {
-            tracing::error!("proxy server failed: {:?}", result);
-            child.kill().await.ok();
-            std::process::exit(1);
+            if !shutdown.is_triggered() {
+                tracing::error!("opencode exited: {:?}, daemon will exit", status);
+                std::process::exit(1);
+            }
+            tracing::warn!("opencode exited while draining: {:?}", status);
         }
-        _ = signal::ctrl_c() => {
-            tracing::info!("received shutdown signal, killing opencode");
-            child.kill().await.ok();
+        result = crate::proxy::serve(OPENCODE_PORT, listen, options, data_dir.to_string_lossy().into_owned(), start_time, teams_handle.clone(), shutdown.clone()) => {
+            if let Err(e) = result {
+                tracing::error!("proxy server failed: {e:?}");
+                child.kill().await.ok();
+                std::process::exit(1);
+            }
         }
     }
 
-    let _ = std::fs::remove_file(&pid_path);
+    tracing::info!("proxy drained, shutting down");
     crate::nodes::deregister(&node_id);
     if let Some(ref url) = server_url {
-        crate::nodes::deregister_remote(url, &node_id).await.ok();
+        match tokio::time::timeout(
+            DEREGISTER_TIMEOUT,
+            crate::nodes::deregister_remote(url, &node_id),
+        )
+        .await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("remote deregistration failed: {e}"),
+            Err(_) => tracing::warn!("remote deregistration timed out"),
+        }
+    }
+    if tokio::time::timeout(TEAMS_FLUSH_TIMEOUT, crate::teams::flush(&teams_handle))
+        .await
+        .is_err()
+    {
+        tracing::warn!("final team flush timed out after {TEAMS_FLUSH_TIMEOUT:?}");
     }
+    stop_opencode(&mut child).await;
+    let _ = std::fs::remove_file(&pid_path);
     Ok(())
 }
 
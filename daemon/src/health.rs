@@ -0,0 +1,298 @@
+//! Liveness and readiness reported at `GET /health` and `GET /ready`.
+//!
+//! Readiness requires opencode to answer HTTP and the team watcher not to have stopped.
+//! Heartbeats, update checks, uptime and the watchdog generation are reported alongside
+//! but don't gate it: a daemon that can't reach the server still serves its clients.
+//! Callers without credentials only learn whether the daemon is ready. The opencode
+//! probe is cached briefly, so hammering `/ready` doesn't turn into upstream traffic.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::teams::now_ms;
+
+pub static HEALTH: Health = Health::new();
+
+/// Incremented across each execve restart by the thaw watchdog.
+pub const GENERATION_ENV: &str = "NIGHTSHIFT_WATCHDOG_GENERATION";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Answered from memory, unlike `/doc`, which makes opencode build its whole spec.
+const PROBE_PATH: &str = "/path";
+/// How long a probe result answers `/ready` before opencode is asked again.
+const PROBE_TTL: Duration = Duration::from_secs(1);
+
+/// Shared so repeated probes reuse one keep-alive connection.
+static PROBE_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .no_proxy()
+        .build()
+        .unwrap_or_default()
+});
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum WatcherState {
+    /// Polling for `~/.claude/teams/` to appear.
+    Waiting,
+    Running,
+    Stopped,
+}
+
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatus {
+    pub enabled: bool,
+    pub last_check_at: Option<u64>,
+    /// Set when the last check failed.
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OpencodeStatus {
+    pub answering: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Readiness {
+    pub ready: bool,
+    pub version: String,
+    pub uptime_secs: u64,
+    pub watchdog_generation: u32,
+    pub opencode: OpencodeStatus,
+    pub teams_watcher: WatcherState,
+    pub last_heartbeat_at: Option<u64>,
+    pub update: UpdateStatus,
+}
+
+pub struct Health {
+    started_at: AtomicU64,
+    watcher: AtomicU8,
+    last_heartbeat_at: AtomicU64,
+    update: Mutex<UpdateStatus>,
+    /// Last probe as `(taken at, port, status)`. Held across the probe so concurrent
+    /// callers share one request instead of each sending their own.
+    last_probe: tokio::sync::Mutex<Option<(Instant, u16, OpencodeStatus)>>,
+}
+
+impl Health {
+    const fn new() -> Self {
+        Self {
+            started_at: AtomicU64::new(0),
+            watcher: AtomicU8::new(WatcherState::Waiting as u8),
+            last_heartbeat_at: AtomicU64::new(0),
+            update: Mutex::new(UpdateStatus {
+                enabled: false,
+                last_check_at: None,
+                last_error: None,
+            }),
+            last_probe: tokio::sync::Mutex::const_new(None),
+        }
+    }
+
+    /// [`probe_opencode`], reusing a result younger than `PROBE_TTL`.
+    pub async fn opencode(&self, port: u16) -> OpencodeStatus {
+        let mut last_probe = self.last_probe.lock().await;
+        if let Some((at, probed_port, status)) = last_probe.as_ref() {
+            if *probed_port == port && at.elapsed() < PROBE_TTL {
+                return status.clone();
+            }
+        }
+        let status = probe_opencode(port).await;
+        *last_probe = Some((Instant::now(), port, status.clone()));
+        status
+    }
+
+    pub fn mark_started(&self, update_enabled: bool) {
+        self.started_at.store(now_ms(), Ordering::Relaxed);
+        self.update_status().enabled = update_enabled;
+    }
+
+    pub fn set_watcher(&self, state: WatcherState) {
+        self.watcher.store(state as u8, Ordering::Relaxed);
+    }
+
+    pub fn watcher(&self) -> WatcherState {
+        match self.watcher.load(Ordering::Relaxed) {
+            s if s == WatcherState::Running as u8 => WatcherState::Running,
+            s if s == WatcherState::Stopped as u8 => WatcherState::Stopped,
+            _ => WatcherState::Waiting,
+        }
+    }
+
+    /// Registration and heartbeat both count: either proves the server knows us.
+    pub fn record_heartbeat(&self) {
+        self.last_heartbeat_at.store(now_ms(), Ordering::Relaxed);
+    }
+
+    pub fn record_update_check(&self, error: Option<String>) {
+        let mut update = self.update_status();
+        update.last_check_at = Some(now_ms());
+        update.last_error = error;
+    }
+
+    fn update_status(&self) -> std::sync::MutexGuard<'_, UpdateStatus> {
+        self.update.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn readiness(&self, opencode: OpencodeStatus) -> Readiness {
+        let teams_watcher = self.watcher();
+        let started_at = self.started_at.load(Ordering::Relaxed);
+        let last_heartbeat_at = self.last_heartbeat_at.load(Ordering::Relaxed);
+        Readiness {
+            ready: opencode.answering && teams_watcher != WatcherState::Stopped,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: match started_at {
+                0 => 0,
+                at => now_ms().saturating_sub(at) / 1000,
+            },
+            watchdog_generation: watchdog_generation(),
+            opencode,
+            teams_watcher,
+            last_heartbeat_at: (last_heartbeat_at != 0).then_some(last_heartbeat_at),
+            update: self.update_status().clone(),
+        }
+    }
+}
+
+pub fn watchdog_generation() -> u32 {
+    std::env::var(GENERATION_ENV)
+        .ok()
+        .and_then(|g| g.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Whether opencode answers HTTP. Any non-5xx response counts: the server is up even
+/// if it doesn't like the request.
+pub async fn probe_opencode(port: u16) -> OpencodeStatus {
+    let url = format!("http://127.0.0.1:{port}{PROBE_PATH}");
+    let started = Instant::now();
+    let result = PROBE_CLIENT.get(&url).send().await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+    match result {
+        Ok(resp) => {
+            let status = resp.status();
+            OpencodeStatus {
+                answering: !status.is_server_error(),
+                status: Some(status.as_u16()),
+                latency_ms,
+                error: None,
+            }
+        }
+        Err(e) => OpencodeStatus {
+            answering: false,
+            status: None,
+            latency_ms,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answering(answering: bool) -> OpencodeStatus {
+        OpencodeStatus {
+            answering,
+            status: answering.then_some(200),
+            latency_ms: Some(1),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn should_gate_readiness_on_opencode_and_watcher() {
+        let health = Health::new();
+        assert!(health.readiness(answering(true)).ready);
+        assert!(!health.readiness(answering(false)).ready);
+
+        health.set_watcher(WatcherState::Running);
+        assert!(health.readiness(answering(true)).ready);
+        health.set_watcher(WatcherState::Stopped);
+        let readiness = health.readiness(answering(true));
+        assert!(!readiness.ready);
+        assert_eq!(readiness.teams_watcher, WatcherState::Stopped);
+    }
+
+    #[test]
+    fn should_report_heartbeat_and_update_checks() {
+        let health = Health::new();
+        health.mark_started(true);
+        let readiness = health.readiness(answering(true));
+        assert_eq!(readiness.last_heartbeat_at, None);
+        assert_eq!(readiness.update.last_check_at, None);
+        assert!(readiness.update.enabled);
+
+        health.record_heartbeat();
+        health.record_update_check(Some("rate limited".into()));
+        let readiness = health.readiness(answering(true));
+        assert!(readiness.last_heartbeat_at.is_some());
+        assert!(readiness.update.last_check_at.is_some());
+        assert_eq!(readiness.update.last_error.as_deref(), Some("rate limited"));
+    }
+
+    #[tokio::test]
+    async fn should_probe_opencode_over_http() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n")
+                .await;
+        });
+        let status = probe_opencode(port).await;
+        assert!(status.answering);
+        assert_eq!(status.status, Some(404));
+
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let status = probe_opencode(closed_port).await;
+        assert!(!status.answering);
+        assert!(status.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn should_reuse_a_recent_probe() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = std::sync::Arc::new(AtomicU64::new(0));
+        let counted = std::sync::Arc::clone(&requests);
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                counted.fetch_add(1, Ordering::Relaxed);
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let health = Health::new();
+        let (first, second) = tokio::join!(health.opencode(port), health.opencode(port));
+        assert!(first.answering && second.answering);
+        assert!(health.opencode(port).await.answering);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        tokio::time::sleep(PROBE_TTL).await;
+        health.opencode(port).await;
+        assert_eq!(requests.load(Ordering::Relaxed), 2);
+    }
+}
@@ -1,11 +1,24 @@
+mod archive;
+mod auth;
+mod backends;
+mod capture;
 mod config;
 mod daemon;
+mod health;
+mod metrics;
 mod nodes;
 mod openapi;
+mod policy;
 mod proxy;
+mod ratelimit;
+mod search;
+mod shutdown;
+mod tasks;
 mod teams;
+mod tls;
 mod toolcalls;
 mod update;
+mod upstream;
 
 use clap::{Parser, Subcommand};
 
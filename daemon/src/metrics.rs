@@ -0,0 +1,284 @@
+//! Process-wide counters and histograms, rendered in the Prometheus text format at
+//! `GET /metrics`.
+
+use crate::teams::WatcherStats;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub static METRICS: Metrics = Metrics::new();
+
+/// Upper bounds in seconds, shared by every histogram.
+const BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if let Some(i) = BUCKETS.iter().position(|b| secs <= *b) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(&self.buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {count}");
+        let braces = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_sum{braces} {sum}");
+        let _ = writeln!(out, "{name}_count{braces} {count}");
+    }
+}
+
+pub struct Metrics {
+    /// Keyed by (method, status).
+    proxy_requests: Mutex<BTreeMap<(String, u16), u64>>,
+    pub proxy_latency: Histogram,
+    proxy_response_bytes: AtomicU64,
+    proxy_upgrades: AtomicU64,
+    pub proxy_bad_gateway: AtomicU64,
+    pub proxy_rate_limited: AtomicU64,
+    pub upstream_connect_retries: AtomicU64,
+    pub upstream_connections_opened: AtomicU64,
+    pub teams_full_scan: Histogram,
+    pub teams_incremental_scan: Histogram,
+    pub diff_refresh: Histogram,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            proxy_requests: Mutex::new(BTreeMap::new()),
+            proxy_latency: Histogram::new(),
+            proxy_response_bytes: AtomicU64::new(0),
+            proxy_upgrades: AtomicU64::new(0),
+            proxy_bad_gateway: AtomicU64::new(0),
+            proxy_rate_limited: AtomicU64::new(0),
+            upstream_connect_retries: AtomicU64::new(0),
+            upstream_connections_opened: AtomicU64::new(0),
+            teams_full_scan: Histogram::new(),
+            teams_incremental_scan: Histogram::new(),
+            diff_refresh: Histogram::new(),
+        }
+    }
+
+    /// `latency` is time to response headers; `bytes` is the response body as streamed.
+    pub fn record_proxy_request(
+        &self,
+        method: &str,
+        status: u16,
+        latency: Duration,
+        bytes: u64,
+        upgrade: bool,
+    ) {
+        *self
+            .proxy_requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((method.to_string(), status))
+            .or_default() += 1;
+        self.proxy_latency.observe(latency);
+        self.proxy_response_bytes
+            .fetch_add(bytes, Ordering::Relaxed);
+        if upgrade {
+            self.proxy_upgrades.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn render(&self, watcher: &WatcherStats) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "nightshift_proxy_requests_total",
+            "counter",
+            "Requests forwarded to opencode",
+        );
+        for ((method, status), count) in self
+            .proxy_requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+        {
+            let _ = writeln!(
+                out,
+                "nightshift_proxy_requests_total{{method=\"{method}\",status=\"{status}\"}} {count}"
+            );
+        }
+        header(
+            &mut out,
+            "nightshift_proxy_request_duration_seconds",
+            "histogram",
+            "Time to upstream response headers",
+        );
+        self.proxy_latency
+            .render(&mut out, "nightshift_proxy_request_duration_seconds", "");
+        counter(
+            &mut out,
+            "nightshift_proxy_response_bytes_total",
+            "Response body bytes streamed back to clients",
+            &self.proxy_response_bytes,
+        );
+        counter(
+            &mut out,
+            "nightshift_proxy_upgrades_total",
+            "Requests upgraded to websockets",
+            &self.proxy_upgrades,
+        );
+        counter(
+            &mut out,
+            "nightshift_proxy_upstream_errors_total",
+            "Requests answered with 502 Bad Gateway",
+            &self.proxy_bad_gateway,
+        );
+        counter(
+            &mut out,
+            "nightshift_proxy_rate_limited_total",
+            "Requests rejected with 429 Too Many Requests",
+            &self.proxy_rate_limited,
+        );
+        counter(
+            &mut out,
+            "nightshift_upstream_connect_retries_total",
+            "Connect attempts retried while opencode starts",
+            &self.upstream_connect_retries,
+        );
+        counter(
+            &mut out,
+            "nightshift_upstream_connections_opened_total",
+            "New TCP connections to opencode",
+            &self.upstream_connections_opened,
+        );
+
+        header(
+            &mut out,
+            "nightshift_teams_scan_duration_seconds",
+            "histogram",
+            "Team watcher rescan durations",
+        );
+        self.teams_full_scan.render(
+            &mut out,
+            "nightshift_teams_scan_duration_seconds",
+            "kind=\"full\"",
+        );
+        self.teams_incremental_scan.render(
+            &mut out,
+            "nightshift_teams_scan_duration_seconds",
+            "kind=\"incremental\"",
+        );
+        header(
+            &mut out,
+            "nightshift_teams_diff_refresh_duration_seconds",
+            "histogram",
+            "Duration of a diff summary refresh over all active members",
+        );
+        self.diff_refresh.render(
+            &mut out,
+            "nightshift_teams_diff_refresh_duration_seconds",
+            "",
+        );
+
+        header(
+            &mut out,
+            "nightshift_teams_full_rescans_with_drift_total",
+            "counter",
+            "Full rescans that found changes incremental rescans missed",
+        );
+        let _ = writeln!(
+            out,
+            "nightshift_teams_full_rescans_with_drift_total {}",
+            watcher.full_rescans_with_drift
+        );
+        header(
+            &mut out,
+            "nightshift_teams_drifted_teams_total",
+            "counter",
+            "Teams changed by full rescans",
+        );
+        let _ = writeln!(
+            out,
+            "nightshift_teams_drifted_teams_total {}",
+            watcher.drifted_teams
+        );
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    header(out, name, "counter", help);
+    let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_render_cumulative_histogram_buckets() {
+        let h = Histogram::new();
+        h.observe(Duration::from_millis(3));
+        h.observe(Duration::from_millis(30));
+        h.observe(Duration::from_secs(30));
+        let mut out = String::new();
+        h.render(&mut out, "x", "kind=\"full\"");
+        assert!(out.contains("x_bucket{kind=\"full\",le=\"0.001\"} 0\n"));
+        assert!(out.contains("x_bucket{kind=\"full\",le=\"0.005\"} 1\n"));
+        assert!(out.contains("x_bucket{kind=\"full\",le=\"0.05\"} 2\n"));
+        assert!(out.contains("x_bucket{kind=\"full\",le=\"10\"} 2\n"));
+        assert!(out.contains("x_bucket{kind=\"full\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("x_count{kind=\"full\"} 3\n"));
+        assert!(out.contains("x_sum{kind=\"full\"} 30.033\n"));
+    }
+
+    #[test]
+    fn should_render_proxy_requests_by_method_and_status() {
+        let metrics = Metrics::new();
+        metrics.record_proxy_request("GET", 200, Duration::from_millis(2), 100, false);
+        metrics.record_proxy_request("GET", 200, Duration::from_millis(2), 50, false);
+        metrics.record_proxy_request("GET", 101, Duration::from_millis(2), 0, true);
+        let out = metrics.render(&WatcherStats::default());
+        assert!(out.contains("nightshift_proxy_requests_total{method=\"GET\",status=\"200\"} 2\n"));
+        assert!(out.contains("nightshift_proxy_requests_total{method=\"GET\",status=\"101\"} 1\n"));
+        assert!(out.contains("nightshift_proxy_response_bytes_total 150\n"));
+        assert!(out.contains("nightshift_proxy_upgrades_total 1\n"));
+        assert!(out.contains("# TYPE nightshift_teams_scan_duration_seconds histogram\n"));
+    }
+}
@@ -13,6 +13,9 @@ pub struct Node {
     pub os: String,
     pub arch: String,
     pub daemon_version: String,
+    /// SHA-256 of the proxy's TLS certificate, for pinning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_fingerprint: Option<String>,
 }
 
 fn nodes_path() -> PathBuf {
@@ -47,11 +50,18 @@ fn write_nodes(nodes: &[Node]) -> Result<()> {
     Ok(())
 }
 
-pub fn register(port: u16, url_override: Option<&str>) -> Result<(String, Node)> {
+pub fn register(
+    port: u16,
+    url_override: Option<&str>,
+    tls_fingerprint: Option<String>,
+) -> Result<(String, Node)> {
     let hostname = get_hostname();
-    let url = url_override
-        .map(String::from)
-        .unwrap_or_else(|| format!("http://localhost:{port}"));
+    let tls = tls_fingerprint.is_some();
+    let url = match url_override {
+        Some(url) => advertised_url(url, tls),
+        None if tls => format!("https://localhost:{port}"),
+        None => format!("http://localhost:{port}"),
+    };
     let id = format!("{hostname}-{port}");
 
     let now = time::OffsetDateTime::now_utc();
@@ -67,6 +77,7 @@ pub fn register(port: u16, url_override: Option<&str>) -> Result<(String, Node)>
         os: std::env::consts::OS.to_string(),
         arch: std::env::consts::ARCH.to_string(),
         daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+        tls_fingerprint,
     };
 
     let mut nodes = read_nodes();
@@ -78,6 +89,18 @@ pub fn register(port: u16, url_override: Option<&str>) -> Result<(String, Node)>
     Ok((id, node))
 }
 
+/// The listener only speaks TLS when it's on, so an `http://` publicUrl would send
+/// every client to a handshake failure.
+fn advertised_url(url: &str, tls: bool) -> String {
+    match url.strip_prefix("http://") {
+        Some(rest) if tls => {
+            tracing::warn!("publicUrl {url} is plain http but tls is on, advertising https");
+            format!("https://{rest}")
+        }
+        _ => url.to_string(),
+    }
+}
+
 pub async fn register_remote(server_url: &str, node: &Node) -> Result<()> {
     reqwest::Client::new()
         .post(format!("{server_url}/nodes"))
@@ -129,3 +152,24 @@ pub fn deregister(id: &str) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_advertise_https_when_tls_is_on() {
+        assert_eq!(
+            advertised_url("http://node.example.com:19277", true),
+            "https://node.example.com:19277"
+        );
+        assert_eq!(
+            advertised_url("https://node.example.com", true),
+            "https://node.example.com"
+        );
+        assert_eq!(
+            advertised_url("http://node.example.com", false),
+            "http://node.example.com"
+        );
+    }
+}
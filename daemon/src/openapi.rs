@@ -1,9 +1,15 @@
 use anyhow::{anyhow, Context, Result};
 use oas3::spec::{
-    Components, ObjectOrReference, Operation, Parameter, ParameterIn, PathItem, Server, Spec,
+    Components, ObjectOrReference, Operation, Parameter, ParameterIn, PathItem, Response,
+    SecurityRequirement, SecurityScheme, Server, Spec,
 };
+use std::collections::BTreeMap;
 
-const OPENCODE_OPENAPI_PATH: &str = "/doc";
+use crate::auth::{
+    AuthConfig, CONTENT_SHA256_HEADER, PUBLIC_PATHS, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+
+pub const OPENCODE_OPENAPI_PATH: &str = "/doc";
 
 fn merge_components(into: &mut Components, from: Components) {
     into.schemas.extend(from.schemas);
@@ -19,7 +25,7 @@ fn merge_components(into: &mut Components, from: Components) {
     into.extensions.extend(from.extensions);
 }
 
-fn merge_openapi(mut upstream: Spec, daemon: Spec, proxy_port: u16) -> Spec {
+fn merge_openapi(mut upstream: Spec, daemon: Spec, proxy_port: u16, proxy_tls: bool) -> Spec {
     if let Some(mut daemon_paths) = daemon.paths {
         let upstream_paths = upstream.paths.get_or_insert_default();
         upstream_paths.append(&mut daemon_paths);
@@ -34,7 +40,10 @@ fn merge_openapi(mut upstream: Spec, daemon: Spec, proxy_port: u16) -> Spec {
     }
 
     upstream.servers = vec![Server {
-        url: format!("http://localhost:{proxy_port}"),
+        url: format!(
+            "{}://localhost:{proxy_port}",
+            if proxy_tls { "https" } else { "http" }
+        ),
         description: Some("nightshift daemon proxy".to_string()),
         variables: Default::default(),
         extensions: Default::default(),
@@ -97,10 +106,106 @@ fn normalize_upstream_spec(spec: &mut Spec) {
     }
 }
 
+fn path_item_operations_mut(path: &mut PathItem) -> impl Iterator<Item = &mut Operation> {
+    [
+        &mut path.get,
+        &mut path.put,
+        &mut path.post,
+        &mut path.delete,
+        &mut path.options,
+        &mut path.head,
+        &mut path.patch,
+        &mut path.trace,
+    ]
+    .into_iter()
+    .filter_map(Option::as_mut)
+}
+
+/// Declares the proxy's auth schemes and adds a 401 to every operation, since the
+/// middleware guards upstream routes as well as the daemon's own. Probe endpoints in
+/// `PUBLIC_PATHS` are marked as not needing credentials instead.
+fn document_auth(spec: &mut Spec, auth: &AuthConfig) {
+    let components = spec.components.get_or_insert_default();
+    let mut requirements = Vec::new();
+    if !auth.tokens.is_empty() {
+        components.security_schemes.insert(
+            "bearerAuth".into(),
+            ObjectOrReference::Object(SecurityScheme::Http {
+                description: Some("Token from `auth.tokens` in config.json".into()),
+                scheme: "bearer".into(),
+                bearer_format: None,
+            }),
+        );
+        requirements.push(SecurityRequirement(BTreeMap::from([(
+            "bearerAuth".into(),
+            Vec::new(),
+        )])));
+    }
+    if auth.hmac_secret.is_some() {
+        components.security_schemes.insert(
+            "hmacTimestamp".into(),
+            ObjectOrReference::Object(SecurityScheme::ApiKey {
+                description: Some("Unix seconds, within 5 minutes of the daemon clock".into()),
+                name: TIMESTAMP_HEADER.into(),
+                location: "header".into(),
+            }),
+        );
+        components.security_schemes.insert(
+            "hmacContentSha256".into(),
+            ObjectOrReference::Object(SecurityScheme::ApiKey {
+                description: Some("Hex SHA-256 of the request body (of nothing if empty)".into()),
+                name: CONTENT_SHA256_HEADER.into(),
+                location: "header".into(),
+            }),
+        );
+        components.security_schemes.insert(
+            "hmacSignature".into(),
+            ObjectOrReference::Object(SecurityScheme::ApiKey {
+                description: Some(
+                    "Hex HMAC-SHA256 of `{timestamp}\\n{METHOD}\\n{path?query}\\n{content-sha256}` keyed by `auth.hmacSecret`; each signature is accepted once"
+                        .into(),
+                ),
+                name: SIGNATURE_HEADER.into(),
+                location: "header".into(),
+            }),
+        );
+        requirements.push(SecurityRequirement(BTreeMap::from([
+            ("hmacTimestamp".into(), Vec::new()),
+            ("hmacContentSha256".into(), Vec::new()),
+            ("hmacSignature".into(), Vec::new()),
+        ])));
+    }
+    if auth.allow_loopback {
+        // An empty requirement marks credentials as optional (loopback clients).
+        requirements.push(SecurityRequirement(BTreeMap::new()));
+    }
+    spec.security = requirements;
+
+    let unauthorized = Response {
+        description: Some("Missing or invalid credentials".into()),
+        ..Default::default()
+    };
+    for (name, path) in spec.paths.iter_mut().flat_map(|paths| paths.iter_mut()) {
+        let public = PUBLIC_PATHS.contains(&name.as_str());
+        for op in path_item_operations_mut(path) {
+            if public {
+                op.security = vec![SecurityRequirement(BTreeMap::new())];
+                continue;
+            }
+            op.responses
+                .get_or_insert_default()
+                .entry("401".into())
+                .or_insert_with(|| ObjectOrReference::Object(unauthorized.clone()));
+        }
+    }
+}
+
 pub async fn merged_openapi_spec(
     opencode_port: u16,
     proxy_port: u16,
+    proxy_tls: bool,
     daemon_openapi_json: &str,
+    auth: &AuthConfig,
 ) -> Result<String> {
     let upstream_url = format!("http://127.0.0.1:{opencode_port}{OPENCODE_OPENAPI_PATH}");
     let upstream_raw = reqwest::get(&upstream_url)
@@ -123,6 +228,9 @@ pub async fn merged_openapi_spec(
     })?;
 
     normalize_upstream_spec(&mut upstream_spec);
-    let merged = merge_openapi(upstream_spec, daemon_spec, proxy_port);
+    let mut merged = merge_openapi(upstream_spec, daemon_spec, proxy_port, proxy_tls);
+    if auth.enabled() {
+        document_auth(&mut merged, auth);
+    }
     serde_json::to_string(&merged).context("failed to serialize merged openapi")
 }
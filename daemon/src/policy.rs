@@ -0,0 +1,377 @@
+//! Method + path access policy, configured under `accessPolicy` in `config.json`.
+//!
+//! Rules are checked in order and the first match decides. Requests no rule matches
+//! are allowed, unless `remoteReadOnly` is set and the request is a non-safe method or a
+//! websocket upgrade from a remote client. "Remote" means anything other than loopback or
+//! the Unix socket. Path globs match whole segments: `*` is one segment, `**` any number.
+//!
+//! Paths are percent-decoded and `.`/`..` segments resolved before matching, so
+//! `/session/%2e%2e/config` is checked as `/config`, and the request goes on with that
+//! canonical path so upstream routes exactly what was checked. Paths with an encoded `/`
+//! are refused outright.
+
+use axum::extract::{Request, State};
+use axum::http::{header, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    Allow,
+    Deny,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyRule {
+    /// Empty matches every method.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    pub path: String,
+    pub action: PolicyAction,
+    /// Only apply the rule to remote clients.
+    #[serde(default)]
+    pub remote_only: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessPolicy {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    /// Remote clients may only use GET, HEAD and OPTIONS without upgrading unless a rule
+    /// allows more.
+    #[serde(default)]
+    pub remote_read_only: bool,
+}
+
+impl AccessPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && !self.remote_read_only
+    }
+
+    pub fn decide(&self, method: &Method, path: &str, upgrade: bool, remote: bool) -> PolicyAction {
+        let rule = self.rules.iter().find(|r| {
+            (remote || !r.remote_only)
+                && (r.methods.is_empty()
+                    || r.methods
+                        .iter()
+                        .any(|m| m.eq_ignore_ascii_case(method.as_str())))
+                && glob_match(&r.path, path)
+        });
+        match rule {
+            Some(rule) => rule.action,
+            None if remote && self.remote_read_only && (upgrade || !is_safe(method)) => {
+                PolicyAction::Deny
+            }
+            None => PolicyAction::Allow,
+        }
+    }
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Percent-decodes `path` and resolves dot segments. `None` for paths that are not valid
+/// UTF-8 once decoded or that hide a `/` inside a segment.
+fn normalize_path(path: &str) -> Option<String> {
+    let mut segments: Vec<String> = Vec::new();
+    for raw in path.split('/') {
+        let segment = percent_decode(raw)?;
+        if segment.contains('/') || segment.to_ascii_lowercase().contains("%2f") {
+            return None;
+        }
+        match segment.as_str() {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Re-encodes a path from [`normalize_path`] for the request line, keeping a trailing `/`.
+fn encode_path(normalized: &str, trailing_slash: bool) -> String {
+    let mut out = String::with_capacity(normalized.len());
+    for byte in normalized.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' => out.push(byte as char),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+'
+            | b',' | b';' | b'=' | b':' | b'@' => out.push(byte as char),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    if trailing_slash && normalized != "/" {
+        out.push('/');
+    }
+    out
+}
+
+/// `uri` with its path replaced, query kept.
+fn with_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            match_segments(&pattern[1..], path)
+                || (!path.is_empty() && match_segments(pattern, &path[1..]))
+        }
+        (Some(p), Some(s)) if *p == "*" || p == s => match_segments(&pattern[1..], &path[1..]),
+        _ => false,
+    }
+}
+
+pub async fn enforce_policy(
+    State(policy): State<Arc<AccessPolicy>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let remote = !crate::auth::is_local_peer(req.extensions());
+    let raw = req.uri().path();
+    let canonical = normalize_path(raw).and_then(|path| {
+        let uri = with_path(req.uri(), &encode_path(&path, raw.ends_with('/')))?;
+        Some((path, uri))
+    });
+    let Some((path, uri)) = canonical else {
+        tracing::debug!("access policy refused path {raw}");
+        return (
+            StatusCode::BAD_REQUEST,
+            [("content-type", "application/json")],
+            r#"{"error":"invalid path"}"#,
+        )
+            .into_response();
+    };
+    *req.uri_mut() = uri;
+    let upgrade = req.headers().contains_key(header::UPGRADE);
+    if policy.decide(req.method(), &path, upgrade, remote) == PolicyAction::Deny {
+        tracing::debug!(
+            "access policy denied {} {} (remote: {remote})",
+            req.method(),
+            req.uri().path()
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            [("content-type", "application/json")],
+            r#"{"error":"forbidden by access policy"}"#,
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(json: &str) -> AccessPolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn should_match_segment_globs() {
+        assert!(glob_match("/session/*/shell", "/session/abc/shell"));
+        assert!(!glob_match("/session/*/shell", "/session/abc/def/shell"));
+        assert!(glob_match("/session/**", "/session"));
+        assert!(glob_match("/session/**", "/session/abc/message"));
+        assert!(glob_match("/**/shell", "/session/abc/shell"));
+        assert!(!glob_match("/config", "/config/providers"));
+        assert!(glob_match("/config", "/config/"));
+    }
+
+    #[test]
+    fn should_apply_first_matching_rule() {
+        let p = policy(
+            r#"{"rules":[
+                {"methods":["PATCH","POST"],"path":"/config","action":"deny"},
+                {"path":"/session/*/shell","action":"deny","remoteOnly":true}
+            ]}"#,
+        );
+        assert_eq!(
+            p.decide(&Method::PATCH, "/config", false, false),
+            PolicyAction::Deny
+        );
+        assert_eq!(
+            p.decide(&Method::GET, "/config", false, true),
+            PolicyAction::Allow
+        );
+        assert_eq!(
+            p.decide(&Method::POST, "/session/s1/shell", false, true),
+            PolicyAction::Deny
+        );
+        assert_eq!(
+            p.decide(&Method::POST, "/session/s1/shell", false, false),
+            PolicyAction::Allow
+        );
+    }
+
+    #[test]
+    fn should_normalize_paths_before_matching() {
+        assert_eq!(
+            normalize_path("/session/%2e%2e/config").as_deref(),
+            Some("/config")
+        );
+        assert_eq!(normalize_path("/a/./b//c/").as_deref(), Some("/a/b/c"));
+        assert_eq!(normalize_path("/../../config").as_deref(), Some("/config"));
+        assert_eq!(
+            normalize_path("/%73ession/s1").as_deref(),
+            Some("/session/s1")
+        );
+        assert_eq!(normalize_path("/session/a%2Fb/shell"), None);
+        assert_eq!(normalize_path("/session/a%252fb/shell"), None);
+        assert_eq!(normalize_path("/session/%zz"), None);
+        assert_eq!(normalize_path("/session/%ff"), None);
+
+        let p = policy(r#"{"rules":[{"path":"/session/*/shell","action":"deny"}]}"#);
+        let path = normalize_path("/session/s1/x/%2E%2E/shell").unwrap();
+        assert_eq!(
+            p.decide(&Method::POST, &path, false, true),
+            PolicyAction::Deny
+        );
+    }
+
+    #[test]
+    fn should_reencode_normalized_paths() {
+        assert_eq!(
+            encode_path("/session/a b/ü", false),
+            "/session/a%20b/%C3%BC"
+        );
+        assert_eq!(encode_path("/config", true), "/config/");
+        assert_eq!(encode_path("/", true), "/");
+        assert_eq!(encode_path("/file:x@y", false), "/file:x@y");
+    }
+
+    /// Status and body of a raw GET, so the request line reaches the server unnormalized.
+    async fn raw_get(port: u16, target: &str) -> (u16, String) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        let request = format!("GET {target} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn should_forward_the_path_the_policy_checked() {
+        let policy = Arc::new(policy(r#"{"rules":[{"path":"/config","action":"deny"}]}"#));
+        let app = axum::Router::new()
+            .fallback(|uri: Uri| async move { uri.to_string() })
+            .layer(axum::middleware::from_fn_with_state(policy, enforce_policy));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await
+        });
+
+        assert_eq!(raw_get(port, "/session/%2e%2e/config").await.0, 403);
+        assert_eq!(raw_get(port, "//config").await.0, 403);
+        assert_eq!(raw_get(port, "/session/%2Fconfig").await.0, 400);
+        assert_eq!(
+            raw_get(port, "/session/./s1/x/%2E%2E/a%20b?q=%2e%2e").await,
+            (200, "/session/s1/a%20b?q=%2e%2e".to_string())
+        );
+        assert_eq!(
+            raw_get(port, "/%73ession/").await,
+            (200, "/session/".to_string())
+        );
+    }
+
+    #[test]
+    fn should_block_remote_upgrades_in_read_only_mode() {
+        let p = policy(
+            r#"{"remoteReadOnly":true,"rules":[
+                {"methods":["GET"],"path":"/pty/*/connect","action":"allow"}
+            ]}"#,
+        );
+        assert_eq!(
+            p.decide(&Method::GET, "/event", true, true),
+            PolicyAction::Deny
+        );
+        assert_eq!(
+            p.decide(&Method::GET, "/event", true, false),
+            PolicyAction::Allow
+        );
+        assert_eq!(
+            p.decide(&Method::GET, "/pty/p1/connect", true, true),
+            PolicyAction::Allow
+        );
+    }
+
+    #[test]
+    fn should_block_remote_writes_in_read_only_mode() {
+        let p = policy(
+            r#"{"remoteReadOnly":true,"rules":[
+                {"methods":["POST"],"path":"/session/*/abort","action":"allow"}
+            ]}"#,
+        );
+        assert_eq!(
+            p.decide(&Method::GET, "/session", false, true),
+            PolicyAction::Allow
+        );
+        assert_eq!(
+            p.decide(&Method::POST, "/session", false, true),
+            PolicyAction::Deny
+        );
+        assert_eq!(
+            p.decide(&Method::DELETE, "/teams/alpha", false, true),
+            PolicyAction::Deny
+        );
+        assert_eq!(
+            p.decide(&Method::POST, "/session", false, false),
+            PolicyAction::Allow
+        );
+        assert_eq!(
+            p.decide(&Method::POST, "/session/s1/abort", false, true),
+            PolicyAction::Allow
+        );
+    }
+}
@@ -1,33 +1,60 @@
 use anyhow::{Context, Result};
 use axum::body::Body;
-use axum::extract::{Path, Request, State};
-use axum::http::StatusCode;
+use axum::body::{Bytes, HttpBody};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{Extensions, HeaderMap, StatusCode};
+use axum::middleware;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{any, get};
+use axum::serve::ListenerExt;
 use axum::{Json, Router};
+use futures_util::StreamExt;
 use hyper::body::Incoming;
 use hyper_util::rt::TokioIo;
 use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::net::{TcpListener, TcpStream};
-use utoipa::ToSchema;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use utoipa::{IntoParams, ToSchema};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+use crate::auth::{Auth, AuthConfig};
+use crate::capture::{CaptureConfig, CaptureStore, PendingCapture};
+use crate::health::HEALTH;
+use crate::metrics::METRICS;
+use crate::policy::AccessPolicy;
+use crate::ratelimit::{Admission, ClientKey, RateLimitConfig, RateLimiter};
+use crate::shutdown::Shutdown;
 use crate::teams::TeamsHandle;
+use crate::tls::TlsListener;
+use crate::upstream::{strip_hop_by_hop, UpstreamPool};
 
-const STARTUP_RETRY_WINDOW: Duration = Duration::from_secs(8);
-const STARTUP_MAX_RETRIES: u32 = 5;
-const STARTUP_RETRY_DELAY: Duration = Duration::from_millis(200);
+const TRANSCRIPT_DEFAULT_LIMIT: usize = 100;
+const TRANSCRIPT_MAX_LIMIT: usize = 500;
+const SEARCH_DEFAULT_LIMIT: usize = 50;
+const SEARCH_MAX_LIMIT: usize = 200;
+const CAPTURES_DEFAULT_LIMIT: usize = 50;
+const CAPTURES_MAX_LIMIT: usize = 500;
+/// How long in-flight requests get to finish after a shutdown signal.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 struct AppState {
     opencode_port: u16,
     proxy_port: u16,
+    proxy_tls: bool,
     project_path: Arc<str>,
     daemon_openapi_json: Arc<str>,
-    start_time: std::time::Instant,
+    upstream: Arc<UpstreamPool>,
     teams: TeamsHandle,
+    auth: Arc<AuthConfig>,
+    captures: Option<Arc<CaptureStore>>,
+    limiter: Option<Arc<RateLimiter>>,
+    shutdown: Shutdown,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, ToSchema)]
@@ -42,6 +69,57 @@ struct NightshiftErrorResponse {
     error: String,
 }
 
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TranscriptQuery {
+    /// Opaque cursor from a previous page's `nextCursor`
+    cursor: Option<String>,
+    /// Page size (default 100, max 500)
+    limit: Option<usize>,
+}
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CaptureListQuery {
+    /// Max captures, newest first (default 50, max 500)
+    limit: Option<usize>,
+}
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchParams {
+    /// Free-text query; each term must match
+    q: String,
+    /// Restrict to one team
+    team: Option<String>,
+    /// Restrict to one member
+    member: Option<String>,
+    /// Restrict to one tool (case-insensitive)
+    tool: Option<String>,
+    /// Max hits (default 50, max 200)
+    limit: Option<usize>,
+}
+
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TeamListParams {
+    /// Only archived (`true`) or only active (`false`) teams
+    archived: Option<bool>,
+    /// By archive time; newest first when omitted
+    order: Option<SortOrder>,
+    /// Teams to skip
+    offset: Option<usize>,
+    /// Page size; all teams when omitted
+    limit: Option<usize>,
+}
+
+#[derive(serde::Deserialize, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
 fn json_response(status: StatusCode, body: String) -> Response {
     Response::builder()
         .status(status)
@@ -66,10 +144,142 @@ async fn get_project_absolute_path(State(state): State<AppState>) -> impl IntoRe
     get,
     path = "/teams",
     operation_id = "daemon.teams.list",
-    responses((status = 200, description = "Team summaries", body = [crate::teams::TeamSummary]))
+    params(TeamListParams),
+    responses(
+        (
+            status = 200,
+            description = "Team summaries, newest first by default",
+            body = [crate::teams::TeamSummary],
+            headers(("x-total-count" = usize, description = "Teams matching the filter before paging"))
+        ),
+        (status = 400, description = "Invalid query parameters")
+    )
+)]
+async fn get_teams(
+    State(state): State<AppState>,
+    Query(params): Query<TeamListParams>,
+) -> Response {
+    let query = crate::teams::TeamListQuery {
+        archived: params.archived,
+        ascending: params.order == Some(SortOrder::Asc),
+        offset: params.offset.unwrap_or(0),
+        limit: params.limit,
+    };
+    let (teams, total) = crate::teams::list_teams(&state.teams, &query).await;
+    let mut response = Json(teams).into_response();
+    response
+        .headers_mut()
+        .insert("x-total-count", total.to_string().parse().unwrap());
+    response
+}
+
+#[utoipa::path(
+    delete,
+    path = "/teams/{team}",
+    operation_id = "daemon.teams.delete",
+    params(("team" = String, Path, description = "Team name or archive id")),
+    responses(
+        (status = 204, description = "Archive deleted"),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse),
+        (status = 409, description = "Team is still active", body = NightshiftErrorResponse)
+    )
+)]
+async fn delete_team(State(state): State<AppState>, Path(team): Path<String>) -> Response {
+    use crate::teams::DeleteArchiveResult;
+    match crate::teams::delete_archive(&state.teams, &team).await {
+        DeleteArchiveResult::Deleted => StatusCode::NO_CONTENT.into_response(),
+        DeleteArchiveResult::NotFound => {
+            json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into())
+        }
+        DeleteArchiveResult::Active => json_response(
+            StatusCode::CONFLICT,
+            r#"{"error":"only archived teams can be deleted"}"#.into(),
+        ),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/teams/{team}/export",
+    operation_id = "daemon.teams.export",
+    params(("team" = String, Path, description = "Team name or archive id")),
+    responses(
+        (status = 200, description = "tar.gz of summary, usage, diffs, patches and tool history", content_type = "application/gzip", body = Vec<u8>),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
+)]
+async fn export_team(State(state): State<AppState>, Path(team): Path<String>) -> Response {
+    match crate::teams::export_team(&state.teams, &team).await {
+        Some(bytes) => {
+            let filename = crate::archive::path_component(&team).replace('"', "_");
+            Response::builder()
+                .header("content-type", "application/gzip")
+                .header(
+                    "content-disposition",
+                    format!("attachment; filename=\"{filename}.tar.gz\""),
+                )
+                .body(Body::from(bytes))
+                .unwrap()
+        }
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/teams/{team}/usage",
+    operation_id = "daemon.teams.usage",
+    params(("team" = String, Path, description = "Team name or archive id")),
+    responses(
+        (status = 200, description = "Token usage per member and team total", body = crate::teams::TeamUsage),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_team_usage(State(state): State<AppState>, Path(team): Path<String>) -> Response {
+    match crate::teams::get_team_usage(&state.teams, &team).await {
+        Some(usage) => Json(usage).into_response(),
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/teams/{team}/tasks/{id}/history",
+    operation_id = "daemon.teams.task.history",
+    params(
+        ("team" = String, Path, description = "Team name or archive id"),
+        ("id" = String, Path, description = "Task id")
+    ),
+    responses(
+        (status = 200, description = "Observed status/owner transitions with lead and cycle time", body = crate::tasks::TaskHistory),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_task_history(
+    State(state): State<AppState>,
+    Path((team, id)): Path<(String, String)>,
+) -> Response {
+    match crate::teams::get_task_history(&state.teams, &team, &id).await {
+        Some(history) => Json(history).into_response(),
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/teams/{team}/tasks/metrics",
+    operation_id = "daemon.teams.task.metrics",
+    params(("team" = String, Path, description = "Team name or archive id")),
+    responses(
+        (status = 200, description = "Lead and cycle time per task and per member", body = crate::tasks::TeamTaskMetrics),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
 )]
-async fn get_teams(State(state): State<AppState>) -> impl IntoResponse {
-    Json(crate::teams::get_teams_summary(&state.teams).await)
+async fn get_task_metrics(State(state): State<AppState>, Path(team): Path<String>) -> Response {
+    match crate::teams::get_task_metrics(&state.teams, &team).await {
+        Some(metrics) => Json(metrics).into_response(),
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
 }
 
 #[utoipa::path(
@@ -77,7 +287,7 @@ async fn get_teams(State(state): State<AppState>) -> impl IntoResponse {
     path = "/teams/{team}/members/{name}/diff",
     operation_id = "daemon.teams.member.diff",
     params(
-        ("team" = String, Path, description = "Team name"),
+        ("team" = String, Path, description = "Team name or archive id"),
         ("name" = String, Path, description = "Member name")
     ),
     responses(
@@ -100,7 +310,7 @@ async fn get_member_diff(
     path = "/teams/{team}/members/{name}/tools",
     operation_id = "daemon.teams.member.tools",
     params(
-        ("team" = String, Path, description = "Team name"),
+        ("team" = String, Path, description = "Team name or archive id"),
         ("name" = String, Path, description = "Member name")
     ),
     responses(
@@ -118,11 +328,279 @@ async fn get_member_tools(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/teams/{team}/members/{name}/tools/{id}",
+    operation_id = "daemon.teams.member.tool",
+    params(
+        ("team" = String, Path, description = "Team name or archive id"),
+        ("name" = String, Path, description = "Member name"),
+        ("id" = String, Path, description = "Tool call id")
+    ),
+    responses(
+        (status = 200, description = "Full tool call input and output", body = crate::toolcalls::ToolCallDetail),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_member_tool(
+    State(state): State<AppState>,
+    Path((team, name, id)): Path<(String, String, String)>,
+) -> Response {
+    match crate::teams::get_member_tool_detail(&state.teams, &team, &name, &id).await {
+        Some(detail) => Json(detail).into_response(),
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/teams/{team}/members/{name}/transcript",
+    operation_id = "daemon.teams.member.transcript",
+    params(
+        ("team" = String, Path, description = "Team name or archive id"),
+        ("name" = String, Path, description = "Member name"),
+        TranscriptQuery
+    ),
+    responses(
+        (status = 200, description = "Member conversation transcript page", body = crate::toolcalls::MemberTranscript),
+        (status = 404, description = "Not found", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_member_transcript(
+    State(state): State<AppState>,
+    Path((team, name)): Path<(String, String)>,
+    Query(query): Query<TranscriptQuery>,
+) -> Response {
+    let limit = query
+        .limit
+        .unwrap_or(TRANSCRIPT_DEFAULT_LIMIT)
+        .clamp(1, TRANSCRIPT_MAX_LIMIT);
+    match crate::teams::get_member_transcript(
+        &state.teams,
+        &team,
+        &name,
+        query.cursor.as_deref(),
+        limit,
+    )
+    .await
+    {
+        Some(transcript) => Json(transcript).into_response(),
+        None => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    operation_id = "daemon.search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Matching tool calls and transcript messages, best first", body = [crate::search::SearchHit]),
+        (status = 400, description = "Empty query", body = NightshiftErrorResponse)
+    )
+)]
+async fn search(State(state): State<AppState>, Query(params): Query<SearchParams>) -> Response {
+    if params.q.trim().is_empty() {
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"q is required"}"#.into(),
+        );
+    }
+    let query = crate::search::SearchQuery {
+        q: &params.q,
+        team: params.team.as_deref(),
+        member: params.member.as_deref(),
+        tool: params.tool.as_deref(),
+        limit: params
+            .limit
+            .unwrap_or(SEARCH_DEFAULT_LIMIT)
+            .clamp(1, SEARCH_MAX_LIMIT),
+    };
+    Json(crate::teams::search(&state.teams, &query).await).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/watcher/stats",
+    operation_id = "daemon.watcher.stats",
+    responses((status = 200, description = "Team watcher rescan counters", body = crate::teams::WatcherStats))
+)]
+async fn get_watcher_stats(State(state): State<AppState>) -> impl IntoResponse {
+    Json(crate::teams::watcher_stats(&state.teams).await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/debug/captures",
+    operation_id = "daemon.debug.captures",
+    params(CaptureListQuery),
+    responses(
+        (status = 200, description = "Captured proxy exchanges, newest first", body = [crate::capture::CaptureSummary]),
+        (status = 403, description = "Caller is not on this machine", body = NightshiftErrorResponse),
+        (status = 404, description = "Capture mode is off", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_captures(
+    State(state): State<AppState>,
+    extensions: Extensions,
+    Query(query): Query<CaptureListQuery>,
+) -> Response {
+    let store = match local_captures(&state, &extensions) {
+        Ok(store) => store,
+        Err((status, body)) => return json_response(status, body.into()),
+    };
+    let limit = query
+        .limit
+        .unwrap_or(CAPTURES_DEFAULT_LIMIT)
+        .clamp(1, CAPTURES_MAX_LIMIT);
+    let store = Arc::clone(store);
+    match tokio::task::spawn_blocking(move || store.list(limit)).await {
+        Ok(captures) => Json(captures).into_response(),
+        Err(e) => {
+            tracing::warn!("listing captures failed: {e}");
+            json_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                r#"{"error":"listing captures failed"}"#.into(),
+            )
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/debug/captures/{id}",
+    operation_id = "daemon.debug.capture",
+    params(("id" = u64, Path, description = "Capture id")),
+    responses(
+        (status = 200, description = "Captured request and response", body = crate::capture::Capture),
+        (status = 403, description = "Caller is not on this machine", body = NightshiftErrorResponse),
+        (status = 404, description = "Not found or capture mode is off", body = NightshiftErrorResponse)
+    )
+)]
+async fn get_capture(
+    State(state): State<AppState>,
+    extensions: Extensions,
+    Path(id): Path<u64>,
+) -> Response {
+    let store = match local_captures(&state, &extensions) {
+        Ok(store) => store,
+        Err((status, body)) => return json_response(status, body.into()),
+    };
+    let store = Arc::clone(store);
+    match tokio::task::spawn_blocking(move || store.get(id)).await {
+        Ok(Some(capture)) => Json(capture).into_response(),
+        Ok(None) => json_response(StatusCode::NOT_FOUND, r#"{"error":"not found"}"#.into()),
+        Err(e) => {
+            tracing::warn!("reading capture failed: {e}");
+            json_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                r#"{"error":"reading capture failed"}"#.into(),
+            )
+        }
+    }
+}
+
+/// Captures hold unredacted bodies, so they are only served to callers on this machine,
+/// whatever the auth config.
+fn local_captures<'a>(
+    state: &'a AppState,
+    extensions: &Extensions,
+) -> Result<&'a Arc<CaptureStore>, (StatusCode, &'static str)> {
+    if !crate::auth::is_local_peer(extensions) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            r#"{"error":"captures are only served to local clients"}"#,
+        ));
+    }
+    state
+        .captures
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, r#"{"error":"capture disabled"}"#))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    operation_id = "daemon.metrics",
+    responses((status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain; version=0.0.4"))
+)]
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let watcher = crate::teams::watcher_stats(&state.teams).await;
+    (
+        [("content-type", "text/plain; version=0.0.4")],
+        METRICS.render(&watcher),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "daemon.health",
+    responses((status = 200, description = "The daemon process is up"))
+)]
+async fn get_health() -> impl IntoResponse {
+    json_response(StatusCode::OK, r#"{"status":"ok"}"#.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    operation_id = "daemon.ready",
+    responses(
+        (status = 200, description = "Ready to proxy. Callers without credentials only get `ready`", body = crate::health::Readiness),
+        (status = 503, description = "opencode is not answering or the team watcher stopped", body = crate::health::Readiness)
+    )
+)]
+async fn get_ready(State(state): State<AppState>, extensions: Extensions) -> Response {
+    let opencode = HEALTH.opencode(state.opencode_port).await;
+    let readiness = HEALTH.readiness(opencode);
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    if extensions.get::<crate::auth::Authenticated>().is_none() {
+        return (status, Json(json!({ "ready": readiness.ready }))).into_response();
+    }
+    (status, Json(readiness)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/teams/events",
+    operation_id = "daemon.teams.events",
+    responses(
+        (status = 200, description = "Server-sent stream of team events", content_type = "text/event-stream", body = crate::teams::TeamEvent)
+    )
+)]
+async fn get_team_events(
+    State(state): State<AppState>,
+) -> Sse<impl futures_util::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    let rx = crate::teams::subscribe(&state.teams).await;
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let event = Event::default().json_data(&event).unwrap_or_default();
+                    return Some((Ok(event), rx));
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::debug!("team events subscriber lagged by {n}");
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 async fn get_openapi_spec(State(state): State<AppState>) -> Response {
     match crate::openapi::merged_openapi_spec(
         state.opencode_port,
         state.proxy_port,
+        state.proxy_tls,
         &state.daemon_openapi_json,
+        &state.auth,
     )
     .await
     {
@@ -141,28 +619,139 @@ async fn get_openapi_spec(State(state): State<AppState>) -> Response {
     }
 }
 
+/// Emits the access log line and proxy metrics once the response body has been
+/// streamed (or dropped by the client).
+struct AccessLog {
+    method: String,
+    path: String,
+    status: u16,
+    latency: Duration,
+    bytes: u64,
+    upgrade: bool,
+    capture: Option<PendingCapture>,
+    /// Releases the rate limiter's in-flight slot when the body ends.
+    _admission: Option<Admission>,
+}
+
+impl AccessLog {
+    fn record_chunk(&mut self, chunk: &Bytes) {
+        self.bytes += chunk.len() as u64;
+        if let Some(ref mut capture) = self.capture {
+            if capture.push_response(chunk) {
+                if let Some(capture) = self.capture.take() {
+                    capture.finish();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AccessLog {
+    fn drop(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            capture.finish();
+        }
+        tracing::info!(
+            target: "nightshift_daemon::access",
+            method = %self.method,
+            path = %self.path,
+            status = self.status,
+            latency_ms = self.latency.as_millis() as u64,
+            bytes = self.bytes,
+            upgrade = self.upgrade,
+            "proxied"
+        );
+        METRICS.record_proxy_request(
+            &self.method,
+            self.status,
+            self.latency,
+            self.bytes,
+            self.upgrade,
+        );
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    METRICS.proxy_rate_limited.fetch_add(1, Ordering::Relaxed);
+    let secs = retry_after.as_secs_f64().ceil().clamp(1.0, 3600.0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [
+            ("content-type", "application/json".to_string()),
+            ("retry-after", secs.to_string()),
+        ],
+        r#"{"error":"rate limited"}"#,
+    )
+        .into_response()
+}
+
 async fn proxy_fallback(State(state): State<AppState>, mut req: Request) -> Response {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
     let is_upgrade = req.headers().contains_key(hyper::header::UPGRADE);
+    let long_lived = crate::ratelimit::is_long_lived(req.headers());
+
+    let mut admission = None;
+    if let Some(ref limiter) = state.limiter {
+        let (parts, body) = req.into_parts();
+        let client = ClientKey::of(&parts);
+        match limiter.admit(client, long_lived) {
+            Ok(admitted) => admission = Some(admitted),
+            Err(retry_after) => {
+                tracing::debug!("rate limited {method} {path} from {client:?}");
+                return too_many_requests(retry_after);
+            }
+        }
+        req = Request::from_parts(parts, body);
+    }
+
     let on_client_upgrade = if is_upgrade {
         Some(hyper::upgrade::on(&mut req))
     } else {
         None
     };
 
-    match forward(req, state.opencode_port, state.start_time).await {
+    let mut capture = None;
+    if let Some(ref store) = state.captures {
+        let (parts, body) = req.into_parts();
+        let pending = store.begin(&parts);
+        // Only wrap bodies that exist. A streamed empty body would make
+        // hyper send bodiless GETs upstream with `transfer-encoding: chunked`.
+        let body = if body.is_end_stream() {
+            body
+        } else {
+            pending.tap_request(body)
+        };
+        req = Request::from_parts(parts, body);
+        capture = Some(pending);
+    }
+
+    match forward(req, &state.upstream).await {
         Ok(mut resp) => {
             if resp.status() == StatusCode::SWITCHING_PROTOCOLS {
                 if let Some(on_client) = on_client_upgrade {
                     let on_upstream = hyper::upgrade::on(&mut resp);
+                    // The stream slot is held until the websocket pipe closes.
+                    let admission = admission.take();
+                    let pipe = state.shutdown.track_pipe();
+                    let shutdown = state.shutdown.clone();
                     tokio::spawn(async move {
+                        let _admission = admission;
+                        let _pipe = pipe;
                         match tokio::try_join!(on_client, on_upstream) {
                             Ok((client, upstream)) => {
                                 let mut client = TokioIo::new(client);
                                 let mut upstream = TokioIo::new(upstream);
-                                if let Err(e) =
-                                    tokio::io::copy_bidirectional(&mut client, &mut upstream).await
-                                {
-                                    tracing::debug!("ws pipe closed: {e}");
+                                tokio::select! {
+                                    result = tokio::io::copy_bidirectional(&mut client, &mut upstream) => {
+                                        if let Err(e) = result {
+                                            tracing::debug!("ws pipe closed: {e}");
+                                        }
+                                    }
+                                    _ = shutdown.triggered() => {
+                                        tracing::debug!("ws pipe closed for shutdown");
+                                    }
                                 }
                             }
                             Err(e) => tracing::warn!("upgrade failed: {e}"),
@@ -170,10 +759,65 @@ async fn proxy_fallback(State(state): State<AppState>, mut req: Request) -> Resp
                     });
                 }
             }
-            resp.map(Body::new)
+            let switched = resp.status() == StatusCode::SWITCHING_PROTOCOLS;
+            strip_hop_by_hop(resp.headers_mut(), switched);
+            if let Some(ref mut capture) = capture {
+                capture.set_response(resp.status().as_u16(), resp.headers(), is_upgrade);
+            }
+            let mut log = AccessLog {
+                method,
+                path,
+                status: resp.status().as_u16(),
+                latency: started.elapsed(),
+                bytes: 0,
+                upgrade: is_upgrade,
+                capture,
+                _admission: admission,
+            };
+            // SSE responses never end on their own, so cut them at
+            // shutdown instead of letting them hold the drain until its deadline.
+            let shutdown = state.shutdown.clone();
+            let cut_off = async move {
+                if long_lived {
+                    shutdown.triggered().await
+                } else {
+                    std::future::pending().await
+                }
+            };
+            resp.map(|body| {
+                Body::from_stream(
+                    Body::new(body)
+                        .into_data_stream()
+                        .map(move |chunk| {
+                            if let Ok(ref bytes) = chunk {
+                                log.record_chunk(bytes);
+                            }
+                            chunk
+                        })
+                        .take_until(cut_off),
+                )
+            })
         }
         Err(e) => {
             tracing::warn!("proxy error: {e:#}");
+            METRICS.proxy_bad_gateway.fetch_add(1, Ordering::Relaxed);
+            if let Some(ref mut capture) = capture {
+                capture.set_response(
+                    StatusCode::BAD_GATEWAY.as_u16(),
+                    &HeaderMap::new(),
+                    is_upgrade,
+                );
+            }
+            drop(AccessLog {
+                method,
+                path,
+                status: StatusCode::BAD_GATEWAY.as_u16(),
+                latency: started.elapsed(),
+                bytes: 0,
+                upgrade: is_upgrade,
+                capture,
+                _admission: admission,
+            });
             (StatusCode::BAD_GATEWAY, format!("proxy error: {e}")).into_response()
         }
     }
@@ -181,8 +825,7 @@ async fn proxy_fallback(State(state): State<AppState>, mut req: Request) -> Resp
 
 async fn forward(
     mut req: Request,
-    opencode_port: u16,
-    start_time: std::time::Instant,
+    upstream: &Arc<UpstreamPool>,
 ) -> Result<hyper::Response<Incoming>> {
     let path = req
         .uri()
@@ -190,48 +833,30 @@ async fn forward(
         .map(|pq| pq.as_str())
         .unwrap_or("/");
     *req.uri_mut() = path.parse()?;
-
-    let stream = {
-        let mut attempts = 0u32;
-        loop {
-            match TcpStream::connect(("127.0.0.1", opencode_port)).await {
-                Ok(s) => break s,
-                Err(e)
-                    if e.kind() == std::io::ErrorKind::ConnectionRefused
-                        && attempts < STARTUP_MAX_RETRIES
-                        && start_time.elapsed() < STARTUP_RETRY_WINDOW =>
-                {
-                    attempts += 1;
-                    tracing::debug!(
-                        "opencode not ready yet (attempt {}), retrying in {:?}",
-                        attempts,
-                        STARTUP_RETRY_DELAY
-                    );
-                    tokio::time::sleep(STARTUP_RETRY_DELAY).await;
-                }
-                Err(e) => return Err(e).context("opencode unreachable"),
-            }
-        }
-    };
-
-    let io = TokioIo::new(stream);
-    let (mut sender, conn) = hyper::client::conn::http1::handshake(io).await?;
-    tokio::spawn(async move {
-        if let Err(e) = conn.with_upgrades().await {
-            tracing::debug!("upstream connection ended: {e}");
-        }
-    });
-
-    let resp = sender.send_request(req).await?;
-    Ok(resp)
+    upstream.send(req).await
 }
 
 fn api_router() -> Router<AppState> {
     let (documented_router, _) = OpenApiRouter::new()
         .routes(routes!(get_project_absolute_path))
         .routes(routes!(get_teams))
+        .routes(routes!(delete_team))
+        .routes(routes!(export_team))
+        .routes(routes!(get_task_history))
+        .routes(routes!(get_task_metrics))
+        .routes(routes!(get_team_events))
+        .routes(routes!(search))
+        .routes(routes!(get_watcher_stats))
+        .routes(routes!(get_health))
+        .routes(routes!(get_ready))
+        .routes(routes!(get_metrics))
+        .routes(routes!(get_captures))
+        .routes(routes!(get_capture))
+        .routes(routes!(get_team_usage))
         .routes(routes!(get_member_diff))
         .routes(routes!(get_member_tools))
+        .routes(routes!(get_member_tool))
+        .routes(routes!(get_member_transcript))
         .split_for_parts();
 
     documented_router
@@ -244,39 +869,250 @@ fn daemon_openapi_json() -> Result<String> {
     let (_, daemon_openapi) = OpenApiRouter::new()
         .routes(routes!(get_project_absolute_path))
         .routes(routes!(get_teams))
+        .routes(routes!(delete_team))
+        .routes(routes!(export_team))
+        .routes(routes!(get_task_history))
+        .routes(routes!(get_task_metrics))
+        .routes(routes!(get_team_events))
+        .routes(routes!(search))
+        .routes(routes!(get_watcher_stats))
+        .routes(routes!(get_health))
+        .routes(routes!(get_ready))
+        .routes(routes!(get_metrics))
+        .routes(routes!(get_captures))
+        .routes(routes!(get_capture))
+        .routes(routes!(get_team_usage))
         .routes(routes!(get_member_diff))
         .routes(routes!(get_member_tools))
+        .routes(routes!(get_member_tool))
+        .routes(routes!(get_member_transcript))
         .split_for_parts();
     daemon_openapi
         .to_json()
         .context("failed to serialize axum daemon openapi")
 }
 
+pub struct Listen {
+    /// `None` keeps the proxy off TCP entirely.
+    pub bind_address: Option<String>,
+    pub port: u16,
+    pub unix_socket: Option<PathBuf>,
+    /// Terminates TLS on the TCP listener; the Unix socket stays plaintext.
+    pub tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
+}
+
+/// Request handling options from `config.json`.
+pub struct ProxyOptions {
+    pub auth: AuthConfig,
+    pub capture: Option<CaptureConfig>,
+    pub policy: AccessPolicy,
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
 pub async fn serve(
     opencode_port: u16,
-    listen_port: u16,
+    listen: Listen,
+    options: ProxyOptions,
     project_path: String,
     start_time: std::time::Instant,
     teams: TeamsHandle,
+    shutdown: Shutdown,
 ) -> Result<()> {
-    let listener = TcpListener::bind(("0.0.0.0", listen_port))
-        .await
-        .with_context(|| format!("failed to bind proxy to :{listen_port}"))?;
+    let ProxyOptions {
+        auth,
+        capture,
+        policy,
+        rate_limit,
+    } = options;
+    let listen_port = listen.port;
+    let tcp_listener = match listen.bind_address {
+        Some(ref addr) => {
+            let listener = TcpListener::bind((addr.as_str(), listen_port))
+                .await
+                .with_context(|| format!("failed to bind proxy to {addr}:{listen_port}"))?;
+            let scheme = if listen.tls.is_some() {
+                "https"
+            } else {
+                "http"
+            };
+            tracing::info!(
+                "proxy listening on {scheme}://{addr}:{listen_port} -> :{opencode_port}"
+            );
+            Some(listener)
+        }
+        None => None,
+    };
+
+    #[cfg(unix)]
+    let unix_listener = match listen.unix_socket {
+        Some(ref path) => {
+            let listener = bind_unix_socket(path)?;
+            tracing::info!("proxy listening on {} -> :{opencode_port}", path.display());
+            Some(listener)
+        }
+        None => None,
+    };
+    #[cfg(not(unix))]
+    if listen.unix_socket.is_some() {
+        tracing::warn!("unixSocket is not supported on this platform, ignoring");
+    }
 
-    tracing::info!("proxy listening on :{listen_port} -> :{opencode_port}");
+    #[cfg(unix)]
+    let has_listener = tcp_listener.is_some() || unix_listener.is_some();
+    #[cfg(not(unix))]
+    let has_listener = tcp_listener.is_some();
+    if !has_listener {
+        anyhow::bail!("proxy has no listener: set bindAddress or unixSocket");
+    }
+
+    if !auth.enabled() {
+        tracing::warn!("proxy auth disabled: no tokens or hmacSecret in config.json");
+    }
 
     let daemon_openapi_json = daemon_openapi_json()?;
 
-    let app = api_router().with_state(AppState {
-        opencode_port,
-        proxy_port: listen_port,
-        project_path: Arc::<str>::from(project_path),
-        daemon_openapi_json: Arc::<str>::from(daemon_openapi_json),
-        start_time,
-        teams,
-    });
+    if !policy.is_empty() {
+        tracing::info!(
+            "access policy: {} rules, remote read-only: {}",
+            policy.rules.len(),
+            policy.remote_read_only
+        );
+    }
+
+    if let Some(ref limits) = rate_limit {
+        tracing::info!(
+            "rate limits: {}/s per client, {}/s global, {} in flight, {} streams",
+            limits.per_client_rps,
+            limits.global_rps,
+            limits.max_in_flight,
+            limits.max_streams
+        );
+    }
+
+    let captures =
+        capture.map(|config| Arc::new(CaptureStore::open(crate::capture::capture_dir(), config)));
+
+    // Layers run outside-in from the last one added, so auth rejects
+    // unauthenticated requests before the policy looks at them.
+    let app = api_router()
+        .layer(middleware::from_fn_with_state(
+            Arc::new(policy),
+            crate::policy::enforce_policy,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(Auth::new(&auth)),
+            crate::auth::require_auth,
+        ))
+        .with_state(AppState {
+            opencode_port,
+            proxy_port: listen_port,
+            proxy_tls: listen.tls.is_some(),
+            project_path: Arc::<str>::from(project_path),
+            daemon_openapi_json: Arc::<str>::from(daemon_openapi_json),
+            upstream: UpstreamPool::new(opencode_port, start_time),
+            teams,
+            auth: Arc::new(auth),
+            captures: captures.clone(),
+            limiter: rate_limit.map(|config| Arc::new(RateLimiter::new(config))),
+            shutdown: shutdown.clone(),
+        });
+
+    let stopped = || {
+        let shutdown = shutdown.clone();
+        async move { shutdown.triggered().await }
+    };
+
+    let tcp = {
+        let app = app.clone();
+        async move {
+            match (tcp_listener, listen.tls) {
+                // Proxied SSE and WebSocket frames are small; don't let Nagle hold them.
+                // `tap_io` is also what gives TLS connections `ConnectInfo<SocketAddr>`.
+                (Some(listener), Some(tls)) => axum::serve(
+                    TlsListener::new(listener, tls)?.tap_io(|tls| {
+                        let _ = tls.get_ref().0.set_nodelay(true);
+                    }),
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(stopped())
+                .await
+                .with_context(|| format!("axum server exited on :{listen_port}")),
+                (Some(listener), None) => axum::serve(
+                    listener.tap_io(|tcp| {
+                        let _ = tcp.set_nodelay(true);
+                    }),
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(stopped())
+                .await
+                .with_context(|| format!("axum server exited on :{listen_port}")),
+                (None, _) => Ok(()),
+            }
+        }
+    };
 
-    axum::serve(listener, app)
-        .await
-        .with_context(|| format!("axum server exited on :{listen_port}"))
+    #[cfg(unix)]
+    let unix = async move {
+        match unix_listener {
+            Some(listener) => axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<crate::auth::UnixPeer>(),
+            )
+            .with_graceful_shutdown(stopped())
+            .await
+            .context("axum server exited on unix socket"),
+            None => Ok(()),
+        }
+    };
+    #[cfg(not(unix))]
+    let unix = async { Ok::<(), anyhow::Error>(()) };
+
+    // Both servers only return early on error; otherwise they finish once the
+    // shutdown signal fired and their connections have drained.
+    let drained = async {
+        tokio::try_join!(tcp, unix)?;
+        shutdown.pipes_closed().await;
+        Ok(())
+    };
+    tokio::pin!(drained);
+    let result = tokio::select! {
+        result = &mut drained => result,
+        _ = shutdown.triggered() => {
+            tracing::info!("draining proxy connections (up to {DRAIN_TIMEOUT:?})");
+            match tokio::time::timeout(DRAIN_TIMEOUT, drained).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!("proxy drain deadline passed, dropping remaining connections");
+                    Ok(())
+                }
+            }
+        }
+    };
+    if let Some(store) = captures {
+        let _ = tokio::task::spawn_blocking(move || store.flush()).await;
+    }
+    result
+}
+
+/// Binds the socket owner-only, replacing a stale socket left by a previous run.
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("failed to bind proxy to {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("failed to restrict {}", path.display()))?;
+    Ok(listener)
 }
@@ -0,0 +1,356 @@
+//! Token-bucket rate limits and concurrency caps for requests proxied to opencode,
+//! configured under `rateLimit` in `config.json`.
+//!
+//! A request first takes an in-flight slot, held until its response body ends, and then a
+//! token from its client's bucket and from the global bucket. A request turned away for
+//! want of a slot costs no tokens; one turned away by a bucket gives its slot back.
+//! Long-lived SSE and WebSocket connections hold a stream slot instead, so a few open
+//! event streams cannot starve regular traffic and vice versa.
+
+use axum::extract::ConnectInfo;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Buckets idle this long are full again and can be forgotten.
+const CLIENT_IDLE_EVICT: Duration = Duration::from_secs(600);
+const CLIENT_MAP_PRUNE_AT: usize = 1024;
+/// Suggested back-off when a concurrency cap, rather than a bucket, is exhausted.
+const CAPACITY_RETRY_AFTER: Duration = Duration::from_secs(1);
+/// One request a day; anything slower is a typo, not a limit.
+const MIN_RPS: f64 = 1.0 / 86_400.0;
+/// Longest wait ever suggested; `Retry-After` is clamped well below it anyway.
+const MAX_WAIT: Duration = Duration::from_secs(86_400);
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    #[serde(default = "default_per_client_rps")]
+    pub per_client_rps: f64,
+    #[serde(default = "default_per_client_burst")]
+    pub per_client_burst: f64,
+    #[serde(default = "default_global_rps")]
+    pub global_rps: f64,
+    #[serde(default = "default_global_burst")]
+    pub global_burst: f64,
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Open SSE/WebSocket connections, counted apart from `maxInFlight`.
+    #[serde(default = "default_max_streams")]
+    pub max_streams: usize,
+}
+
+impl RateLimitConfig {
+    /// A zero or vanishing rate, a burst below one token or a zero cap would turn every
+    /// request away.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, rps) in [
+            ("perClientRps", self.per_client_rps),
+            ("globalRps", self.global_rps),
+        ] {
+            if !(rps.is_finite() && rps >= MIN_RPS) {
+                anyhow::bail!("rateLimit.{name} must be at least {MIN_RPS} (one request a day)");
+            }
+        }
+        for (name, burst) in [
+            ("perClientBurst", self.per_client_burst),
+            ("globalBurst", self.global_burst),
+        ] {
+            if !(burst.is_finite() && burst >= 1.0) {
+                anyhow::bail!("rateLimit.{name} must be at least 1");
+            }
+        }
+        for (name, cap) in [
+            ("maxInFlight", self.max_in_flight),
+            ("maxStreams", self.max_streams),
+        ] {
+            if cap == 0 {
+                anyhow::bail!("rateLimit.{name} must be at least 1");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_per_client_rps() -> f64 {
+    20.0
+}
+
+fn default_per_client_burst() -> f64 {
+    40.0
+}
+
+fn default_global_rps() -> f64 {
+    200.0
+}
+
+fn default_global_burst() -> f64 {
+    400.0
+}
+
+fn default_max_in_flight() -> usize {
+    64
+}
+
+fn default_max_streams() -> usize {
+    32
+}
+
+/// Who a request is charged to. Unix socket peers share one bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    Ip(IpAddr),
+    Unix,
+}
+
+impl ClientKey {
+    pub fn of(parts: &Parts) -> Self {
+        parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| ClientKey::Ip(addr.ip()))
+            .unwrap_or(ClientKey::Unix)
+    }
+}
+
+/// Websocket upgrades and SSE subscriptions stay open for the life of a UI tab.
+pub fn is_long_lived(headers: &HeaderMap) -> bool {
+    headers.contains_key(axum::http::header::UPGRADE)
+        || headers
+            .get(axum::http::header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("text/event-stream"))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn full(burst: f64, now: Instant) -> Self {
+        Self {
+            tokens: burst,
+            last: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.last = now;
+    }
+
+    /// Time until one token is available, zero if one is available now.
+    fn wait(&self, rate: f64) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64((1.0 - self.tokens) / rate)
+                .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+        }
+    }
+}
+
+struct Buckets {
+    global: Bucket,
+    clients: HashMap<ClientKey, Bucket>,
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+    in_flight: Arc<Semaphore>,
+    streams: Arc<Semaphore>,
+}
+
+/// Held for as long as the request counts against a concurrency cap.
+pub struct Admission {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            buckets: Mutex::new(Buckets {
+                global: Bucket::full(config.global_burst, now),
+                clients: HashMap::new(),
+            }),
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight)),
+            streams: Arc::new(Semaphore::new(config.max_streams)),
+            config,
+        }
+    }
+
+    /// Admits the request or returns how long the client should wait (`Retry-After`).
+    pub fn admit(&self, client: ClientKey, long_lived: bool) -> Result<Admission, Duration> {
+        let slots = if long_lived {
+            &self.streams
+        } else {
+            &self.in_flight
+        };
+        let permit = Arc::clone(slots)
+            .try_acquire_owned()
+            .map_err(|_| CAPACITY_RETRY_AFTER)?;
+        // The permit is released again if the buckets say no.
+        self.take_token(client, Instant::now())?;
+        Ok(Admission { _permit: permit })
+    }
+
+    fn take_token(&self, client: ClientKey, now: Instant) -> Result<(), Duration> {
+        let c = &self.config;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.clients.len() >= CLIENT_MAP_PRUNE_AT {
+            buckets
+                .clients
+                .retain(|_, b| now.saturating_duration_since(b.last) < CLIENT_IDLE_EVICT);
+        }
+
+        let Buckets { global, clients } = &mut *buckets;
+        let client = clients
+            .entry(client)
+            .or_insert_with(|| Bucket::full(c.per_client_burst, now));
+        client.refill(c.per_client_rps, c.per_client_burst, now);
+        global.refill(c.global_rps, c.global_burst, now);
+
+        let wait = client.wait(c.per_client_rps).max(global.wait(c.global_rps));
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        client.tokens -= 1.0;
+        global.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_client: (f64, f64), global: (f64, f64)) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_client_rps: per_client.0,
+            per_client_burst: per_client.1,
+            global_rps: global.0,
+            global_burst: global.1,
+            max_in_flight: 2,
+            max_streams: 1,
+        })
+    }
+
+    fn ip(last: u8) -> ClientKey {
+        ClientKey::Ip(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[test]
+    fn should_limit_each_client_to_its_burst_then_refill() {
+        let limiter = limiter((2.0, 3.0), (100.0, 100.0));
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.take_token(ip(1), now).is_ok());
+        }
+        let wait = limiter.take_token(ip(1), now).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(limiter.take_token(ip(2), now).is_ok());
+        assert!(limiter
+            .take_token(ip(1), now + Duration::from_millis(500))
+            .is_ok());
+    }
+
+    #[test]
+    fn should_cap_waits_for_tiny_or_zero_rates() {
+        let empty = Bucket {
+            tokens: 0.0,
+            last: Instant::now(),
+        };
+        assert_eq!(empty.wait(1e-300), MAX_WAIT);
+        assert_eq!(empty.wait(0.0), MAX_WAIT);
+        assert_eq!(empty.wait(-1.0), MAX_WAIT);
+        assert_eq!(empty.wait(2.0), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn should_share_the_global_bucket_across_clients() {
+        let limiter = limiter((100.0, 100.0), (1.0, 2.0));
+        let now = Instant::now();
+        assert!(limiter.take_token(ip(1), now).is_ok());
+        assert!(limiter.take_token(ip(2), now).is_ok());
+        assert_eq!(
+            limiter.take_token(ClientKey::Unix, now).unwrap_err(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn should_treat_upgrades_and_event_streams_as_long_lived() {
+        let parts = |name: &str, value: &str| {
+            axum::http::Request::get("/event")
+                .header(name, value)
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+        assert!(is_long_lived(&parts("upgrade", "websocket").headers));
+        assert!(is_long_lived(&parts("accept", "text/event-stream").headers));
+        assert!(!is_long_lived(&parts("accept", "application/json").headers));
+        assert_eq!(ClientKey::of(&parts("accept", "*/*")), ClientKey::Unix);
+    }
+
+    #[test]
+    fn should_cap_requests_and_streams_separately() {
+        let limiter = limiter((100.0, 100.0), (100.0, 100.0));
+        let a = limiter.admit(ip(1), false).unwrap();
+        let _b = limiter.admit(ip(1), false).unwrap();
+        assert_eq!(
+            limiter.admit(ip(1), false).err(),
+            Some(CAPACITY_RETRY_AFTER)
+        );
+
+        let _stream = limiter.admit(ip(1), true).unwrap();
+        assert!(limiter.admit(ip(2), true).is_err());
+
+        drop(a);
+        assert!(limiter.admit(ip(1), false).is_ok());
+    }
+
+    #[test]
+    fn should_not_charge_tokens_for_requests_over_capacity() {
+        let limiter = limiter((0.001, 3.0), (100.0, 100.0));
+        let a = limiter.admit(ip(1), false).unwrap();
+        let _stream = limiter.admit(ip(1), true).unwrap();
+        for _ in 0..5 {
+            assert_eq!(limiter.admit(ip(1), true).err(), Some(CAPACITY_RETRY_AFTER));
+        }
+        drop(a);
+        // Two of the three tokens went to admitted requests, none to the rejected ones.
+        assert!(limiter.admit(ip(1), false).is_ok());
+        assert!(limiter.take_token(ip(1), Instant::now()).is_err());
+    }
+
+    #[test]
+    fn should_reject_configs_that_admit_nothing() {
+        let config = |rps: f64, burst: f64, cap: usize| RateLimitConfig {
+            per_client_rps: rps,
+            per_client_burst: burst,
+            global_rps: 100.0,
+            global_burst: 100.0,
+            max_in_flight: cap,
+            max_streams: 1,
+        };
+        assert!(config(1.0, 1.0, 1).validate().is_ok());
+        assert!(config(0.0, 10.0, 1).validate().is_err());
+        assert!(config(-1.0, 10.0, 1).validate().is_err());
+        assert!(config(1e-300, 10.0, 1).validate().is_err());
+        assert!(config(f64::NAN, 10.0, 1).validate().is_err());
+        assert!(config(1.0, 0.5, 1).validate().is_err());
+        assert!(config(1.0, 10.0, 0).validate().is_err());
+    }
+}
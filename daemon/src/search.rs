@@ -0,0 +1,428 @@
+//! Full-text index over tool calls and transcript text across active and archived
+//! teams, backed by SQLite FTS5 at `~/.nightshift/search.db`.
+
+use crate::toolcalls::{ToolCall, TranscriptBlock, TranscriptMessage};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+const SNIPPET_TOKENS: i64 = 16;
+
+pub struct SearchIndex {
+    conn: Mutex<Connection>,
+}
+
+/// One indexed unit: a tool call or a transcript message.
+pub struct SearchDoc {
+    pub member: String,
+    pub tool: Option<String>,
+    pub kind: &'static str,
+    pub ref_id: String,
+    pub timestamp: u64,
+    pub body: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Team name, or archive id for archived teams
+    pub team: String,
+    pub member: String,
+    pub tool: Option<String>,
+    /// `"tool_call"` or `"transcript"`
+    pub kind: String,
+    /// Tool call id or transcript message id
+    pub ref_id: String,
+    pub timestamp: u64,
+    pub snippet: String,
+}
+
+#[derive(Default)]
+pub struct SearchQuery<'a> {
+    pub q: &'a str,
+    pub team: Option<&'a str>,
+    pub member: Option<&'a str>,
+    pub tool: Option<&'a str>,
+    pub limit: usize,
+}
+
+impl SearchIndex {
+    pub fn open(path: &Path) -> Option<Self> {
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                tracing::warn!("failed to create search index dir: {e}");
+                return None;
+            }
+        }
+        let conn = match Connection::open(path) {
+            Ok(c) => c,
+            Err(e) => {
+                tracing::warn!("failed to open search index {}: {e}", path.display());
+                return None;
+            }
+        };
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Option<Self> {
+        Self::init(Connection::open_in_memory().ok()?)
+    }
+
+    fn init(conn: Connection) -> Option<Self> {
+        // Only `body` is tokenized; the rest ride along for filtering
+        // and for pointing hits back at the tool/transcript endpoints.
+        let schema = "CREATE VIRTUAL TABLE IF NOT EXISTS docs USING fts5(
+            body,
+            team UNINDEXED,
+            member UNINDEXED,
+            tool UNINDEXED,
+            kind UNINDEXED,
+            ref_id UNINDEXED,
+            timestamp UNINDEXED
+        )";
+        if let Err(e) = conn.execute_batch(schema) {
+            tracing::warn!("failed to create search index schema: {e}");
+            return None;
+        }
+        Some(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Replace everything indexed for `team` with `docs`.
+    pub fn replace_team(&self, team: &str, docs: &[SearchDoc]) {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM docs WHERE team = ?1", params![team])?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT INTO docs (body, team, member, tool, kind, ref_id, timestamp)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )?;
+                for doc in docs {
+                    stmt.execute(params![
+                        doc.body,
+                        team,
+                        doc.member,
+                        doc.tool,
+                        doc.kind,
+                        doc.ref_id,
+                        doc.timestamp as i64,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })();
+        if let Err(e) = result {
+            tracing::warn!("failed to index team '{team}': {e}");
+        }
+    }
+
+    /// Drop everything indexed under a team key not in `keys`, e.g. archives deleted
+    /// while the daemon was down.
+    pub fn retain_teams(&self, keys: &[&str]) {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let result = (|| -> rusqlite::Result<()> {
+            let indexed: Vec<String> = {
+                let mut stmt = conn.prepare("SELECT DISTINCT team FROM docs")?;
+                let rows = stmt.query_map([], |row| row.get(0))?;
+                rows.flatten().collect()
+            };
+            for team in indexed.iter().filter(|t| !keys.contains(&t.as_str())) {
+                conn.execute("DELETE FROM docs WHERE team = ?1", params![team])?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            tracing::warn!("failed to prune search index: {e}");
+        }
+    }
+
+    pub fn has_team(&self, team: &str) -> bool {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.query_row(
+            "SELECT 1 FROM docs WHERE team = ?1 LIMIT 1",
+            params![team],
+            |_| Ok(()),
+        )
+        .optional()
+        .ok()
+        .flatten()
+        .is_some()
+    }
+
+    /// Best matches first. An empty or all-punctuation query matches nothing.
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+        let Some(fts) = fts_query(query.q) else {
+            return Vec::new();
+        };
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = match conn.prepare(
+            "SELECT team, member, tool, kind, ref_id, timestamp,
+                    snippet(docs, 0, '', '', '…', ?6)
+             FROM docs
+             WHERE docs MATCH ?1
+               AND (?2 IS NULL OR team = ?2 OR substr(team, 1, length(?2) + 1) = ?2 || '@')
+               AND (?3 IS NULL OR member = ?3)
+               AND (?4 IS NULL OR lower(tool) = lower(?4))
+             ORDER BY rank
+             LIMIT ?5",
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("failed to prepare search query: {e}");
+                return Vec::new();
+            }
+        };
+        let rows = stmt.query_map(
+            params![
+                fts,
+                query.team,
+                query.member,
+                query.tool,
+                query.limit as i64,
+                SNIPPET_TOKENS,
+            ],
+            |row| {
+                Ok(SearchHit {
+                    team: row.get(0)?,
+                    member: row.get(1)?,
+                    tool: row.get(2)?,
+                    kind: row.get(3)?,
+                    ref_id: row.get(4)?,
+                    timestamp: row.get::<_, i64>(5)? as u64,
+                    snippet: row.get(6)?,
+                })
+            },
+        );
+        match rows {
+            Ok(rows) => rows.flatten().collect(),
+            Err(e) => {
+                tracing::warn!("search query failed: {e}");
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl SearchDoc {
+    pub fn from_tool_call(member: &str, call: &ToolCall) -> Self {
+        // Archived calls only keep the summary.
+        let input = if call.input_text.is_empty() {
+            &call.input_summary
+        } else {
+            &call.input_text
+        };
+        let mut body = format!("{} {input}", call.tool);
+        if let Some(ref title) = call.title {
+            body.push(' ');
+            body.push_str(title);
+        }
+        Self {
+            member: member.to_string(),
+            tool: Some(call.tool.clone()),
+            kind: "tool_call",
+            ref_id: call.id.clone(),
+            timestamp: call.timestamp,
+            body,
+        }
+    }
+
+    /// `None` for messages without any text, e.g. bare tool results.
+    pub fn from_transcript(member: &str, message: &TranscriptMessage) -> Option<Self> {
+        let body = message
+            .blocks
+            .iter()
+            .filter_map(|b| match b {
+                TranscriptBlock::Text { text } | TranscriptBlock::Reasoning { text } => {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if body.trim().is_empty() {
+            return None;
+        }
+        Some(Self {
+            member: member.to_string(),
+            tool: None,
+            kind: "transcript",
+            ref_id: message.id.clone(),
+            timestamp: message.timestamp,
+            body,
+        })
+    }
+}
+
+/// Turn free text into an FTS5 query: every whitespace-separated term becomes a
+/// quoted phrase, so `auth.rs` matches the adjacent tokens `auth rs` and operators
+/// in user input are taken literally.
+fn fts_query(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split_whitespace()
+        .filter(|t| t.chars().any(char::is_alphanumeric))
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+pub fn index_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".nightshift/search.db")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(member: &str, tool: Option<&str>, kind: &'static str, body: &str) -> SearchDoc {
+        SearchDoc {
+            member: member.to_string(),
+            tool: tool.map(str::to_string),
+            kind,
+            ref_id: format!("{member}-{body}"),
+            timestamp: 1,
+            body: body.to_string(),
+        }
+    }
+
+    fn query(q: &str) -> SearchQuery<'_> {
+        SearchQuery {
+            q,
+            limit: 50,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_find_file_paths_and_commands_across_teams() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        index.replace_team(
+            "alpha",
+            &[
+                doc("w1", Some("Edit"), "tool_call", "Edit src/auth.rs"),
+                doc("w2", Some("Bash"), "tool_call", "Bash cargo test"),
+            ],
+        );
+        index.replace_team(
+            "beta",
+            &[doc(
+                "w3",
+                None,
+                "transcript",
+                "ready to run cargo publish now",
+            )],
+        );
+
+        let hits = index.search(&query("auth.rs"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].team, "alpha");
+        assert_eq!(hits[0].member, "w1");
+
+        let hits = index.search(&query("cargo publish"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].team, "beta");
+        assert_eq!(hits[0].kind, "transcript");
+    }
+
+    #[test]
+    fn should_index_the_full_tool_input() {
+        let input = serde_json::json!({
+            "command": format!("cd daemon && {} && cargo test --features tls", "true ".repeat(20)),
+        });
+        let call = ToolCall {
+            id: "t1".into(),
+            tool: "bash".into(),
+            title: None,
+            input_summary: crate::toolcalls::summarize_input("bash", &input),
+            status: "completed".into(),
+            timestamp: 1,
+            duration_ms: None,
+            input_text: crate::toolcalls::input_text(&input),
+        };
+        assert!(!call.input_summary.contains("features"));
+
+        let index = SearchIndex::open_in_memory().unwrap();
+        index.replace_team("alpha", &[SearchDoc::from_tool_call("w1", &call)]);
+        let hits = index.search(&query("--features tls"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].ref_id, "t1");
+    }
+
+    #[test]
+    fn should_filter_by_team_member_and_tool() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        index.replace_team(
+            "alpha",
+            &[
+                doc("w1", Some("Bash"), "tool_call", "Bash cargo build"),
+                doc(
+                    "w2",
+                    Some("Bash"),
+                    "tool_call",
+                    "Bash cargo build --release",
+                ),
+                doc("w2", None, "transcript", "cargo build passed"),
+            ],
+        );
+        index.replace_team(
+            "beta",
+            &[doc("w1", Some("Bash"), "tool_call", "Bash cargo build")],
+        );
+
+        let q = SearchQuery {
+            team: Some("alpha"),
+            member: Some("w2"),
+            tool: Some("bash"),
+            ..query("cargo")
+        };
+        let hits = index.search(&q);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].ref_id, "w2-Bash cargo build --release");
+    }
+
+    #[test]
+    fn should_replace_team_docs_on_reindex() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        index.replace_team("alpha", &[doc("w1", None, "transcript", "old text")]);
+        index.replace_team("alpha", &[doc("w1", None, "transcript", "new text")]);
+        assert!(index.search(&query("old")).is_empty());
+        assert_eq!(index.search(&query("new")).len(), 1);
+        assert!(index.has_team("alpha"));
+        assert!(!index.has_team("beta"));
+    }
+
+    #[test]
+    fn should_match_archives_by_team_name() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        index.replace_team("alpha@1", &[doc("w1", None, "transcript", "deploy")]);
+        index.replace_team("alpha", &[doc("w1", None, "transcript", "deploy")]);
+        index.replace_team("alphabet", &[doc("w1", None, "transcript", "deploy")]);
+        let q = SearchQuery {
+            team: Some("alpha"),
+            ..query("deploy")
+        };
+        let mut teams: Vec<String> = index.search(&q).into_iter().map(|h| h.team).collect();
+        teams.sort();
+        assert_eq!(teams, vec!["alpha", "alpha@1"]);
+
+        index.retain_teams(&["alpha"]);
+        assert!(!index.has_team("alpha@1"));
+        assert!(index.has_team("alpha"));
+    }
+
+    #[test]
+    fn should_treat_query_operators_literally() {
+        assert_eq!(fts_query("a OR \"b"), Some("\"a\" \"OR\" \"\"\"b\"".into()));
+        assert_eq!(fts_query("  - * "), None);
+    }
+}
@@ -0,0 +1,119 @@
+//! Coordinated shutdown on SIGTERM/SIGINT.
+//!
+//! The proxy stops accepting connections when [`Shutdown::trigger`] fires and drains
+//! what is in flight. Websocket pipes live outside hyper's connection tracking, so each
+//! holds a [`PipeGuard`] and the drain also waits for [`Shutdown::pipes_closed`].
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    pipes: Arc<watch::Sender<usize>>,
+}
+
+/// Counts one open websocket pipe until dropped.
+pub struct PipeGuard {
+    pipes: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for PipeGuard {
+    fn drop(&mut self) {
+        self.pipes.send_modify(|n| *n -= 1);
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::channel(false).0),
+            pipes: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolves once [`Shutdown::trigger`] has been called, immediately if it already was.
+    pub async fn triggered(&self) {
+        let mut rx = self.triggered.subscribe();
+        // The sender lives in `self`, so this only errors if it is dropped mid-wait.
+        let _ = rx.wait_for(|t| *t).await;
+    }
+
+    pub fn track_pipe(&self) -> PipeGuard {
+        self.pipes.send_modify(|n| *n += 1);
+        PipeGuard {
+            pipes: Arc::clone(&self.pipes),
+        }
+    }
+
+    pub async fn pipes_closed(&self) {
+        let mut rx = self.pipes.subscribe();
+        let _ = rx.wait_for(|n| *n == 0).await;
+    }
+}
+
+/// Resolves on the first SIGINT (Ctrl-C) or SIGTERM.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => tracing::info!("received SIGINT"),
+                    _ = sigterm.recv() => tracing::info!("received SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("failed to install SIGTERM handler: {e}"),
+        }
+    }
+    if tokio::signal::ctrl_c().await.is_ok() {
+        tracing::info!("received SIGINT");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn should_resolve_triggered_for_late_waiters() {
+        let shutdown = Shutdown::new();
+        assert!(!shutdown.is_triggered());
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.triggered().await }
+        });
+        shutdown.trigger();
+        waiter.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), shutdown.triggered())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_open_pipes() {
+        let shutdown = Shutdown::new();
+        shutdown.pipes_closed().await;
+
+        let a = shutdown.track_pipe();
+        let b = shutdown.track_pipe();
+        drop(a);
+        let pending = tokio::time::timeout(Duration::from_millis(50), shutdown.pipes_closed());
+        assert!(pending.await.is_err());
+        drop(b);
+        tokio::time::timeout(Duration::from_secs(1), shutdown.pipes_closed())
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+//! Task timelines: lead time and cycle time derived from the status transitions the
+//! team watcher observes.
+
+use crate::teams::{ArchivedTask, TaskTransition};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+const IN_PROGRESS: &str = "in_progress";
+const COMPLETED: &str = "completed";
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskHistory {
+    pub team: String,
+    pub id: String,
+    pub subject: String,
+    pub transitions: Vec<TaskTransition>,
+    #[serde(flatten)]
+    pub times: TaskTimes,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTimes {
+    /// First seen to first completed.
+    pub lead_time_ms: Option<u64>,
+    /// First in progress to the completion that followed.
+    pub cycle_time_ms: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskMetrics {
+    pub id: String,
+    pub subject: String,
+    pub owner: Option<String>,
+    #[serde(flatten)]
+    pub times: TaskTimes,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberTaskMetrics {
+    pub completed: u32,
+    pub avg_lead_time_ms: Option<u64>,
+    pub avg_cycle_time_ms: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamTaskMetrics {
+    pub team: String,
+    pub tasks: Vec<TaskMetrics>,
+    /// Keyed by the task owner at completion.
+    pub members: BTreeMap<String, MemberTaskMetrics>,
+}
+
+pub fn task_times(transitions: &[TaskTransition]) -> TaskTimes {
+    let Some(first) = transitions.first() else {
+        return TaskTimes::default();
+    };
+    let completed = transitions.iter().find(|t| t.status == COMPLETED);
+    let started = transitions.iter().find(|t| t.status == IN_PROGRESS);
+    TaskTimes {
+        lead_time_ms: completed.map(|c| c.at.saturating_sub(first.at)),
+        cycle_time_ms: started.and_then(|s| {
+            transitions
+                .iter()
+                .find(|t| t.status == COMPLETED && t.at >= s.at)
+                .map(|c| c.at - s.at)
+        }),
+    }
+}
+
+/// Owner when the task first completed; reassigning it afterwards doesn't move the credit.
+fn completed_by(transitions: &[TaskTransition]) -> Option<&str> {
+    transitions
+        .iter()
+        .find(|t| t.status == COMPLETED)?
+        .owner
+        .as_deref()
+}
+
+pub fn team_metrics(team: &str, tasks: &[ArchivedTask]) -> TeamTaskMetrics {
+    let mut lead: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    let mut cycle: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    for task in tasks {
+        let times = task_times(&task.history);
+        let (Some(owner), Some(lead_time)) = (completed_by(&task.history), times.lead_time_ms)
+        else {
+            continue;
+        };
+        lead.entry(owner.to_string()).or_default().push(lead_time);
+        if let Some(cycle_time) = times.cycle_time_ms {
+            cycle.entry(owner.to_string()).or_default().push(cycle_time);
+        }
+    }
+    let members = lead
+        .into_iter()
+        .map(|(owner, leads)| {
+            let metrics = MemberTaskMetrics {
+                completed: leads.len() as u32,
+                avg_lead_time_ms: average(&leads),
+                avg_cycle_time_ms: cycle.get(&owner).and_then(|c| average(c)),
+            };
+            (owner, metrics)
+        })
+        .collect();
+
+    TeamTaskMetrics {
+        team: team.to_string(),
+        tasks: tasks
+            .iter()
+            .map(|t| TaskMetrics {
+                id: t.id.clone(),
+                subject: t.subject.clone(),
+                owner: t.owner.clone(),
+                times: task_times(&t.history),
+            })
+            .collect(),
+        members,
+    }
+}
+
+fn average(values: &[u64]) -> Option<u64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<u64>() / values.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(status: &str, owner: Option<&str>, at: u64) -> TaskTransition {
+        TaskTransition {
+            status: status.to_string(),
+            owner: owner.map(str::to_string),
+            at,
+        }
+    }
+
+    fn task(id: &str, owner: Option<&str>, history: Vec<TaskTransition>) -> ArchivedTask {
+        ArchivedTask {
+            id: id.to_string(),
+            subject: format!("task {id}"),
+            description: String::new(),
+            status: history.last().map(|t| t.status.clone()).unwrap_or_default(),
+            owner: owner.map(str::to_string),
+            blocks: Vec::new(),
+            blocked_by: Vec::new(),
+            history,
+        }
+    }
+
+    #[test]
+    fn should_compute_lead_and_cycle_time() {
+        let times = task_times(&[
+            transition("pending", None, 100),
+            transition("pending", Some("w1"), 150),
+            transition("in_progress", Some("w1"), 200),
+            transition("completed", Some("w1"), 500),
+        ]);
+        assert_eq!(times.lead_time_ms, Some(400));
+        assert_eq!(times.cycle_time_ms, Some(300));
+    }
+
+    #[test]
+    fn should_leave_times_empty_for_unfinished_tasks() {
+        let times = task_times(&[
+            transition("pending", None, 100),
+            transition("in_progress", Some("w1"), 200),
+        ]);
+        assert_eq!(times, TaskTimes::default());
+
+        let skipped_progress = task_times(&[
+            transition("pending", None, 100),
+            transition("completed", Some("w1"), 300),
+        ]);
+        assert_eq!(skipped_progress.lead_time_ms, Some(200));
+        assert_eq!(skipped_progress.cycle_time_ms, None);
+    }
+
+    #[test]
+    fn should_average_per_member_by_completing_owner() {
+        let metrics = team_metrics(
+            "alpha",
+            &[
+                task(
+                    "1",
+                    Some("w1"),
+                    vec![
+                        transition("pending", None, 0),
+                        transition("in_progress", Some("w1"), 100),
+                        transition("completed", Some("w1"), 300),
+                    ],
+                ),
+                task(
+                    "2",
+                    Some("w1"),
+                    vec![
+                        transition("pending", None, 0),
+                        transition("completed", Some("w1"), 400),
+                    ],
+                ),
+                task(
+                    "3",
+                    Some("w2"),
+                    vec![transition("in_progress", Some("w2"), 0)],
+                ),
+                // Reassigned after w3 finished it: still w3's.
+                task(
+                    "4",
+                    Some("w2"),
+                    vec![
+                        transition("pending", None, 0),
+                        transition("completed", Some("w3"), 100),
+                        transition("completed", Some("w2"), 200),
+                    ],
+                ),
+            ],
+        );
+        assert_eq!(metrics.tasks.len(), 4);
+        assert_eq!(
+            metrics.members["w1"],
+            MemberTaskMetrics {
+                completed: 2,
+                avg_lead_time_ms: Some(350),
+                avg_cycle_time_ms: Some(200),
+            }
+        );
+        assert!(!metrics.members.contains_key("w2"));
+        assert_eq!(metrics.members["w3"].completed, 1);
+        assert_eq!(metrics.members["w3"].avg_lead_time_ms, Some(100));
+    }
+}
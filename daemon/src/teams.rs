@@ -1,7 +1,16 @@
-use crate::toolcalls::{self, MemberToolHistory, ToolCall, ToolStats};
+use crate::archive::{ArchiveStore, ArchivedTeam, RetentionPolicy, TeamArchive};
+use crate::backends::{self, AgentBackend, MemberInfo, ResolvedSession, SessionRef};
+use crate::health::{WatcherState, HEALTH};
+use crate::metrics::METRICS;
+use crate::search::{SearchDoc, SearchHit, SearchIndex, SearchQuery};
+use crate::tasks::{TaskHistory, TeamTaskMetrics};
+use crate::toolcalls::{
+    MemberToolHistory, MemberTranscript, TokenUsage, ToolCall, ToolCallDetail, ToolStats,
+};
 use notify::{RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -9,8 +18,14 @@ use utoipa::ToSchema;
 
 const TEAMS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
 const DIFF_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
-const FULL_RESCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+const FULL_RESCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const SESSION_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
 const DEBOUNCE_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
+const OPENCODE_DB_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+const SEARCH_INDEX_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How often team events may retry resolving a member that has no session yet.
+const UNRESOLVED_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
 
 // --- MCP config types (read-only, deserialized from ~/.claude/) ---
 
@@ -32,7 +47,6 @@ struct TeamConfig {
 #[serde(rename_all = "camelCase")]
 struct MemberConfig {
     #[serde(default)]
-    #[allow(dead_code)]
     agent_id: String,
     name: String,
     #[serde(default)]
@@ -42,6 +56,8 @@ struct MemberConfig {
     #[serde(default)]
     cwd: String,
     #[serde(default)]
+    joined_at: Option<u64>,
+    #[serde(default)]
     is_active: Option<bool>,
     #[serde(default)]
     color: Option<String>,
@@ -59,36 +75,30 @@ struct TaskFile {
     id: serde_json::Value,
     subject: String,
     #[serde(default)]
-    #[allow(dead_code)]
     description: String,
     status: String,
     #[serde(default)]
     owner: Option<String>,
     #[serde(default)]
-    #[allow(dead_code)]
     blocks: Vec<serde_json::Value>,
     #[serde(default)]
-    #[allow(dead_code)]
     blocked_by: Vec<serde_json::Value>,
 }
 
-// --- Claude Code active-sessions.json ---
-
-#[derive(Deserialize, Debug)]
-struct ActiveSessions {
-    sessions: HashMap<String, ActiveSession>,
-}
-
-#[derive(Deserialize, Debug)]
-struct ActiveSession {
-    transcript_path: String,
+/// One entry of `~/.claude/teams/{team}/inboxes/{member}.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxMessage {
     #[serde(default)]
-    tmux: Option<TmuxInfo>,
-}
-
-#[derive(Deserialize, Debug)]
-struct TmuxInfo {
-    pane_id: String,
+    pub from: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub read: bool,
 }
 
 // --- Internal state ---
@@ -97,32 +107,82 @@ struct MemberState {
     config: MemberConfig,
     baseline_commit: Option<String>,
     cached_summary: Option<DiffSummary>,
-    session_path: Option<PathBuf>,
+    cached_usage: Option<TokenUsage>,
+    session_live: bool,
+    session: Option<SessionRef>,
+    session_source: Option<&'static str>,
+    /// Size of a file-backed session when last checked, to notice it being truncated.
+    session_len: u64,
+    /// When resolution last ran; bounds retries for members without a session.
+    resolved_at: u64,
+    /// Kept current by the opencode DB watcher so reads skip the full part scan.
+    tool_cache: Option<ToolCache>,
+}
+
+struct ToolCache {
+    calls: Vec<ToolCall>,
+    high_water: u64,
+}
+
+impl MemberState {
+    fn backend_name(&self) -> &str {
+        self.config
+            .backend_type
+            .as_deref()
+            .unwrap_or(backends::DEFAULT_BACKEND)
+    }
+
+    fn backend(&self) -> Option<&'static dyn AgentBackend> {
+        backends::get(self.backend_name())
+    }
 }
 
 struct TeamState {
     config: TeamConfig,
     members: HashMap<String, MemberState>,
     tasks: HashMap<String, TaskFile>,
+    /// Status/owner changes seen by the watcher, keyed by task id.
+    task_history: HashMap<String, Vec<TaskTransition>>,
+    /// Kept after the inbox files go away so the archive still has them.
+    inboxes: HashMap<String, Vec<InboxMessage>>,
 }
 
 pub struct TeamsData {
     active: HashMap<String, TeamState>,
-    archived: HashMap<String, TeamArchive>,
+    /// Keyed by archive id (`<name>@<archived_at>`).
+    archived: HashMap<String, ArchivedTeam>,
+    archive_store: Option<Arc<ArchiveStore>>,
+    retention: RetentionPolicy,
+    events: tokio::sync::broadcast::Sender<TeamEvent>,
+    search: Option<Arc<SearchIndex>>,
+    watcher_stats: WatcherStats,
 }
 
 pub type TeamsHandle = Arc<RwLock<TeamsData>>;
 
-// --- Archive types ---
+/// What a `{team}` path segment refers to.
+#[derive(Debug, Clone, PartialEq)]
+enum TeamKey {
+    Active(String),
+    Archived(String),
+}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-struct TeamArchive {
-    name: String,
-    archived_at: u64,
-    final_state: TeamSummary,
-    member_diffs: HashMap<String, String>,
-    member_tools: HashMap<String, Vec<ToolCall>>,
+impl TeamsData {
+    /// An archive id wins, then an active team name, then the most recent archive
+    /// with that name.
+    fn resolve(&self, key: &str) -> Option<TeamKey> {
+        if self.archived.contains_key(key) {
+            return Some(TeamKey::Archived(key.to_string()));
+        }
+        if self.active.contains_key(key) {
+            return Some(TeamKey::Active(key.to_string()));
+        }
+        self.archived
+            .iter()
+            .filter(|(_, a)| a.final_state.name == key)
+            .max_by_key(|(_, a)| a.final_state.archived_at)
+            .map(|(id, _)| TeamKey::Archived(id.clone()))
+    }
 }
 
 // --- API response types ---
@@ -134,6 +194,13 @@ pub struct TeamSummary {
     pub description: String,
     pub created_at: u64,
     pub archived: bool,
+    /// Unix millis; set for archived teams only.
+    #[serde(default)]
+    pub archived_at: Option<u64>,
+    /// Unique across runs of a team with the same name; use it in place of `name`
+    /// to address an archive. Set for archived teams only.
+    #[serde(default)]
+    pub archive_id: Option<String>,
     pub members: Vec<MemberSummary>,
     pub tasks: Vec<TaskSummary>,
     pub conflicts: Vec<ConflictInfo>,
@@ -149,6 +216,14 @@ pub struct MemberSummary {
     pub is_active: bool,
     pub color: Option<String>,
     pub diff_summary: Option<DiffSummary>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+    /// Whether the member's agent session has written anything recently.
+    #[serde(default)]
+    pub session_live: bool,
+    /// How the session was located, e.g. `tmux` or `project-scan`.
+    #[serde(default)]
+    pub session_source: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -178,6 +253,29 @@ pub struct TaskSummary {
     pub owner: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTransition {
+    pub status: String,
+    pub owner: Option<String>,
+    /// Unix millis when the watcher first saw this state.
+    pub at: u64,
+}
+
+/// A task's full file plus its observed history, as kept in archives and exports.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedTask {
+    pub id: String,
+    pub subject: String,
+    pub description: String,
+    pub status: String,
+    pub owner: Option<String>,
+    pub blocks: Vec<serde_json::Value>,
+    pub blocked_by: Vec<serde_json::Value>,
+    pub history: Vec<TaskTransition>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConflictInfo {
@@ -185,6 +283,14 @@ pub struct ConflictInfo {
     pub members: Vec<String>,
 }
 
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamUsage {
+    pub team: String,
+    pub total: TokenUsage,
+    pub members: HashMap<String, TokenUsage>,
+}
+
 #[derive(Serialize, Debug, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MemberDiffDetail {
@@ -196,16 +302,78 @@ pub struct MemberDiffDetail {
     pub diff: String,
 }
 
+/// How the team watcher is keeping up. Full rescans are a consistency check; drift
+/// means they found changes the event-driven rescans missed.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStats {
+    /// Single-team reloads triggered by filesystem events.
+    pub incremental_rescans: u64,
+    pub full_rescans: u64,
+    pub full_rescans_with_drift: u64,
+    /// Teams changed by full rescans, summed over all of them.
+    pub drifted_teams: u64,
+    pub last_drift_at: Option<u64>,
+}
+
+/// Pushed to `GET /teams/events` subscribers.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TeamEvent {
+    ToolCall {
+        team: String,
+        member: String,
+        call: ToolCall,
+    },
+}
+
 // --- Public API ---
 
-pub fn new_handle() -> TeamsHandle {
-    let archived = load_archives();
+pub fn new_handle(retention: RetentionPolicy) -> TeamsHandle {
+    let archive_store = ArchiveStore::open(&crate::archive::store_path())
+        .or_else(|| {
+            tracing::warn!("archives will not persist across restarts");
+            ArchiveStore::open_in_memory()
+        })
+        .map(Arc::new);
+    let archived = match archive_store {
+        Some(ref store) => {
+            let imported = store.import_json_dir(&crate::archive::legacy_archive_dir());
+            if imported > 0 {
+                tracing::info!("migrated {imported} JSON team archives");
+            }
+            store.prune(&retention, now_ms());
+            store.load_all()
+        }
+        None => HashMap::new(),
+    };
+    let (events, _) = tokio::sync::broadcast::channel(EVENT_CHANNEL_CAPACITY);
     Arc::new(RwLock::new(TeamsData {
         active: HashMap::new(),
         archived,
+        archive_store,
+        retention,
+        events,
+        search: SearchIndex::open(&crate::search::index_path()).map(Arc::new),
+        watcher_stats: WatcherStats::default(),
     }))
 }
 
+/// Final pass before shutdown. Picks up changes still sitting in the watcher's debounce
+/// window, so their task transitions and archives reach the store, then reindexes search.
+pub async fn flush(handle: &TeamsHandle) {
+    rescan(handle).await;
+    refresh_search_index(handle).await;
+}
+
+pub async fn watcher_stats(handle: &TeamsHandle) -> WatcherStats {
+    handle.read().await.watcher_stats.clone()
+}
+
+pub async fn subscribe(handle: &TeamsHandle) -> tokio::sync::broadcast::Receiver<TeamEvent> {
+    handle.read().await.events.subscribe()
+}
+
 pub async fn get_teams_summary(handle: &TeamsHandle) -> Vec<TeamSummary> {
     let data = handle.read().await;
     let mut result = Vec::new();
@@ -221,6 +389,157 @@ pub async fn get_teams_summary(handle: &TeamsHandle) -> Vec<TeamSummary> {
     result
 }
 
+/// Filter, sort and page options for `GET /teams`.
+#[derive(Debug, Default)]
+pub struct TeamListQuery {
+    pub archived: Option<bool>,
+    pub ascending: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// One page of teams plus the total matching before paging. Active teams sort as
+/// newer than any archive; ties fall back to `created_at`.
+pub async fn list_teams(handle: &TeamsHandle, query: &TeamListQuery) -> (Vec<TeamSummary>, usize) {
+    let teams = get_teams_summary(handle).await;
+    page_teams(teams, query)
+}
+
+fn page_teams(mut teams: Vec<TeamSummary>, query: &TeamListQuery) -> (Vec<TeamSummary>, usize) {
+    if let Some(archived) = query.archived {
+        teams.retain(|t| t.archived == archived);
+    }
+    teams.sort_by_key(|t| (t.archived_at.unwrap_or(u64::MAX), t.created_at));
+    if !query.ascending {
+        teams.reverse();
+    }
+    let total = teams.len();
+    let page = teams
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    (page, total)
+}
+
+pub enum DeleteArchiveResult {
+    Deleted,
+    NotFound,
+    /// The team is still running; only archives can be deleted.
+    Active,
+}
+
+pub async fn delete_archive(handle: &TeamsHandle, team_name: &str) -> DeleteArchiveResult {
+    let mut data = handle.write().await;
+    let id = match data.resolve(team_name) {
+        Some(TeamKey::Archived(id)) => id,
+        Some(TeamKey::Active(_)) => return DeleteArchiveResult::Active,
+        None => return DeleteArchiveResult::NotFound,
+    };
+    data.archived.remove(&id);
+    if let Some(ref store) = data.archive_store {
+        store.delete(&id);
+    }
+    if let Some(ref search) = data.search {
+        search.replace_team(&id, &[]);
+    }
+    tracing::info!("archive '{id}' deleted");
+    DeleteArchiveResult::Deleted
+}
+
+/// A tar.gz with the team summary, usage, tasks with their history, and per member
+/// the diff stats, full patch, tool history and inbox. Works for active and archived
+/// teams.
+pub async fn export_team(handle: &TeamsHandle, team_name: &str) -> Option<Vec<u8>> {
+    let (summary, tasks, inboxes) = {
+        let data = handle.read().await;
+        match data.resolve(team_name)? {
+            TeamKey::Active(name) => {
+                let team = data.active.get(&name)?;
+                (
+                    build_team_summary(team, false),
+                    snapshot_tasks(team),
+                    team.inboxes.clone(),
+                )
+            }
+            TeamKey::Archived(id) => {
+                let store = data.archive_store.as_ref()?;
+                (
+                    data.archived.get(&id)?.final_state.clone(),
+                    store.tasks(&id),
+                    store.team_inboxes(&id),
+                )
+            }
+        }
+    };
+    let usage = get_team_usage(handle, team_name).await;
+
+    let mut entries = vec![
+        ("summary.json".to_string(), to_pretty_json(&summary)),
+        ("usage.json".to_string(), to_pretty_json(&usage)),
+        ("tasks.json".to_string(), to_pretty_json(&tasks)),
+    ];
+    for (member, messages) in &inboxes {
+        let dir = format!("members/{}", crate::archive::path_component(member));
+        entries.push((format!("{dir}/inbox.json"), to_pretty_json(messages)));
+    }
+    for member in &summary.members {
+        let dir = format!("members/{}", crate::archive::path_component(&member.name));
+        if let Some(ref stats) = member.diff_summary {
+            entries.push((format!("{dir}/diff-summary.json"), to_pretty_json(stats)));
+        }
+        if let Some(detail) = get_member_diff(handle, team_name, &member.name).await {
+            if !detail.diff.is_empty() {
+                entries.push((format!("{dir}/changes.patch"), detail.diff.into_bytes()));
+            }
+        }
+        if let Some(history) = get_member_tools(handle, team_name, &member.name).await {
+            entries.push((format!("{dir}/tools.json"), to_pretty_json(&history)));
+        }
+    }
+
+    let root = crate::archive::path_component(summary.archive_id.as_deref().unwrap_or(team_name));
+    match crate::archive::tar_gz(&root, &entries) {
+        Ok(bytes) => Some(bytes),
+        Err(e) => {
+            tracing::warn!("failed to export team '{team_name}': {e}");
+            None
+        }
+    }
+}
+
+/// Active teams read the live snapshot, archives their stored task list.
+async fn team_tasks(handle: &TeamsHandle, team_name: &str) -> Option<Vec<ArchivedTask>> {
+    let data = handle.read().await;
+    match data.resolve(team_name)? {
+        TeamKey::Active(name) => Some(snapshot_tasks(data.active.get(&name)?)),
+        TeamKey::Archived(id) => Some(data.archive_store.as_ref()?.tasks(&id)),
+    }
+}
+
+pub async fn get_task_history(
+    handle: &TeamsHandle,
+    team_name: &str,
+    task_id: &str,
+) -> Option<TaskHistory> {
+    let task = team_tasks(handle, team_name)
+        .await?
+        .into_iter()
+        .find(|t| t.id == task_id)?;
+    Some(TaskHistory {
+        team: team_name.to_string(),
+        id: task.id,
+        subject: task.subject,
+        times: crate::tasks::task_times(&task.history),
+        transitions: task.history,
+    })
+}
+
+pub async fn get_task_metrics(handle: &TeamsHandle, team_name: &str) -> Option<TeamTaskMetrics> {
+    let tasks = team_tasks(handle, team_name).await?;
+    Some(crate::tasks::team_metrics(team_name, &tasks))
+}
+
 pub async fn get_member_diff(
     handle: &TeamsHandle,
     team_name: &str,
@@ -228,24 +547,24 @@ pub async fn get_member_diff(
 ) -> Option<MemberDiffDetail> {
     let member_info = {
         let data = handle.read().await;
-        if let Some(team) = data.active.get(team_name) {
-            team.members
+        match data.resolve(team_name)? {
+            TeamKey::Active(name) => data
+                .active
+                .get(&name)?
+                .members
                 .get(member_name)
-                .map(|m| (m.config.cwd.clone(), m.baseline_commit.clone()))
-        } else if let Some(archive) = data.archived.get(team_name) {
-            if let Some(diff) = archive.member_diffs.get(member_name) {
+                .map(|m| (m.config.cwd.clone(), m.baseline_commit.clone())),
+            TeamKey::Archived(id) => {
+                let diff = data.archive_store.as_ref()?.member_diff(&id, member_name)?;
                 return Some(MemberDiffDetail {
                     name: member_name.to_string(),
                     team: team_name.to_string(),
                     cwd: String::new(),
                     baseline_commit: None,
                     current_commit: None,
-                    diff: diff.clone(),
+                    diff,
                 });
             }
-            return None;
-        } else {
-            None
         }
     };
 
@@ -272,53 +591,35 @@ pub async fn get_member_tools(
     team_name: &str,
     member_name: &str,
 ) -> Option<MemberToolHistory> {
-    let member_info = {
+    {
         let data = handle.read().await;
-        if let Some(team) = data.active.get(team_name) {
-            team.members.get(member_name).map(|m| {
-                let backend = m
-                    .config
-                    .backend_type
-                    .as_deref()
-                    .unwrap_or("claude")
-                    .to_string();
-                let session_path = m.session_path.clone();
-                let opencode_session_id = m.config.opencode_session_id.clone();
-                (backend, session_path, opencode_session_id)
-            })
-        } else if let Some(archive) = data.archived.get(team_name) {
-            if let Some(calls) = archive.member_tools.get(member_name) {
-                let stats = ToolStats::from_calls(calls);
-                return Some(MemberToolHistory {
-                    name: member_name.to_string(),
-                    team: team_name.to_string(),
-                    backend: "archived".to_string(),
-                    tool_calls: calls.clone(),
-                    stats,
-                });
-            }
-            return None;
-        } else {
-            None
+        if let TeamKey::Archived(id) = data.resolve(team_name)? {
+            let calls = data
+                .archive_store
+                .as_ref()?
+                .member_tools(&id, member_name)?;
+            let stats = ToolStats::from_calls(&calls);
+            return Some(MemberToolHistory {
+                name: member_name.to_string(),
+                team: team_name.to_string(),
+                backend: "archived".to_string(),
+                tool_calls: calls,
+                stats,
+            });
         }
+    }
+
+    let cached = {
+        let data = handle.read().await;
+        let member = data.active.get(team_name)?.members.get(member_name)?;
+        member.tool_cache.as_ref().map(|c| c.calls.clone())
     };
 
-    let (backend, session_path, opencode_session_id) = member_info?;
-    let calls = match backend.as_str() {
-        "claude" => {
-            if let Some(ref path) = session_path {
-                crate::toolcalls::read_claude_tools(path)
-            } else {
-                Vec::new()
-            }
-        }
-        "opencode" => {
-            if let Some(ref sid) = opencode_session_id {
-                crate::toolcalls::read_opencode_tools(sid)
-            } else {
-                Vec::new()
-            }
-        }
+    let (backend_name, backend, session) =
+        active_member_session(handle, team_name, member_name).await?;
+    let calls = match (cached, backend, session) {
+        (Some(calls), _, _) => calls,
+        (None, Some(backend), Some(session)) => backend.read_tools(&session),
         _ => Vec::new(),
     };
     let stats = ToolStats::from_calls(&calls);
@@ -326,28 +627,142 @@ pub async fn get_member_tools(
     Some(MemberToolHistory {
         name: member_name.to_string(),
         team: team_name.to_string(),
-        backend,
+        backend: backend_name,
         tool_calls: calls,
         stats,
     })
 }
 
-fn read_member_tools(member: &MemberState, backend: &str) -> Vec<ToolCall> {
-    match backend {
-        "opencode" => {
-            if let Some(ref sid) = member.config.opencode_session_id {
-                toolcalls::read_opencode_tools(sid)
-            } else {
-                Vec::new()
-            }
-        }
-        _ => {
-            if let Some(ref path) = member.session_path {
-                toolcalls::read_claude_tools(path)
-            } else {
-                Vec::new()
+pub async fn get_member_tool_detail(
+    handle: &TeamsHandle,
+    team_name: &str,
+    member_name: &str,
+    call_id: &str,
+) -> Option<ToolCallDetail> {
+    // Archives only keep summaries, so full input/output is
+    // available for active teams only.
+    let (_, backend, session) = active_member_session(handle, team_name, member_name).await?;
+    backend?.read_tool_detail(&session?, call_id)
+}
+
+pub async fn get_member_transcript(
+    handle: &TeamsHandle,
+    team_name: &str,
+    member_name: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Option<MemberTranscript> {
+    let (backend_name, backend, session) =
+        active_member_session(handle, team_name, member_name).await?;
+    let messages = match (backend, session) {
+        (Some(backend), Some(session)) => backend.read_transcript(&session),
+        _ => Vec::new(),
+    };
+
+    let start = cursor.and_then(|c| c.parse::<usize>().ok()).unwrap_or(0);
+    let end = start.saturating_add(limit).min(messages.len());
+    let next_cursor = (end < messages.len()).then(|| end.to_string());
+    let page = messages
+        .into_iter()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect();
+
+    Some(MemberTranscript {
+        name: member_name.to_string(),
+        team: team_name.to_string(),
+        backend: backend_name,
+        messages: page,
+        next_cursor,
+    })
+}
+
+pub async fn get_team_usage(handle: &TeamsHandle, team_name: &str) -> Option<TeamUsage> {
+    // Transcripts are read after the guard is dropped; parsing them can take a while.
+    let sessions = {
+        let data = handle.read().await;
+        match data.resolve(team_name)? {
+            TeamKey::Active(name) => data
+                .active
+                .get(&name)?
+                .members
+                .iter()
+                .map(|(name, m)| (name.clone(), m.backend(), m.session.clone()))
+                .collect::<Vec<_>>(),
+            TeamKey::Archived(id) => {
+                let archived = data.archived.get(&id)?;
+                return Some(team_usage(team_name, archived.member_usage.clone()));
             }
         }
+    };
+    let members = sessions
+        .into_iter()
+        .map(|(name, backend, session)| (name, read_usage(backend, session.as_ref())))
+        .collect();
+    Some(team_usage(team_name, members))
+}
+
+fn team_usage(team_name: &str, members: HashMap<String, TokenUsage>) -> TeamUsage {
+    let mut total = TokenUsage::default();
+    for usage in members.values() {
+        total.add(usage);
+    }
+
+    TeamUsage {
+        team: team_name.to_string(),
+        total,
+        members,
+    }
+}
+
+pub async fn search(handle: &TeamsHandle, query: &SearchQuery<'_>) -> Vec<SearchHit> {
+    let index = handle.read().await.search.clone();
+    match index {
+        Some(index) => index.search(query),
+        None => Vec::new(),
+    }
+}
+
+/// Snapshot a member's backend and session so readers run without holding the lock.
+async fn active_member_session(
+    handle: &TeamsHandle,
+    team_name: &str,
+    member_name: &str,
+) -> Option<(
+    String,
+    Option<&'static dyn AgentBackend>,
+    Option<SessionRef>,
+)> {
+    let data = handle.read().await;
+    let member = data.active.get(team_name)?.members.get(member_name)?;
+    Some((
+        member.backend_name().to_string(),
+        member.backend(),
+        member.session.clone(),
+    ))
+}
+
+fn read_member_usage(member: &MemberState) -> TokenUsage {
+    read_usage(member.backend(), member.session.as_ref())
+}
+
+fn read_usage(
+    backend: Option<&'static dyn AgentBackend>,
+    session: Option<&SessionRef>,
+) -> TokenUsage {
+    match (backend, session) {
+        (Some(backend), Some(session)) => backend.read_usage(session),
+        _ => TokenUsage::default(),
+    }
+}
+
+fn read_member_tools(member: &MemberState) -> Vec<ToolCall> {
+    if let Some(ref cache) = member.tool_cache {
+        return cache.calls.clone();
+    }
+    match (member.backend(), &member.session) {
+        (Some(backend), Some(session)) => backend.read_tools(session),
+        _ => Vec::new(),
     }
 }
 
@@ -368,17 +783,19 @@ pub async fn spawn_watcher(handle: TeamsHandle) {
     tracing::info!("discovered ~/.claude/teams/, starting team watcher");
     initial_scan(&handle).await;
 
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<()>(16);
+    // Unbounded so a burst of events can't be dropped; a lost path
+    // would leave its team stale until the next full rescan.
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Vec<PathBuf>>();
 
-    let tx_clone = tx.clone();
     let mut watcher = match notify::recommended_watcher(move |res: Result<notify::Event, _>| {
-        if let Ok(_event) = res {
-            let _ = tx_clone.try_send(());
+        if let Ok(event) = res {
+            let _ = tx.send(event.paths);
         }
     }) {
         Ok(w) => w,
         Err(e) => {
             tracing::warn!("failed to create filesystem watcher: {e}");
+            HEALTH.set_watcher(WatcherState::Stopped);
             return;
         }
     };
@@ -392,6 +809,8 @@ pub async fn spawn_watcher(handle: TeamsHandle) {
         }
     }
 
+    tokio::spawn(spawn_opencode_watcher(handle.clone()));
+
     let diff_handle = handle.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(DIFF_REFRESH_INTERVAL);
@@ -401,22 +820,196 @@ pub async fn spawn_watcher(handle: TeamsHandle) {
         }
     });
 
+    let session_handle = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SESSION_REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh_session_stats(&session_handle).await;
+        }
+    });
+
+    let search_handle = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SEARCH_INDEX_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh_search_index(&search_handle).await;
+        }
+    });
+
     let rescan_handle = handle.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(FULL_RESCAN_INTERVAL);
+        interval.tick().await;
         loop {
             interval.tick().await;
-            rescan(&rescan_handle).await;
+            let started = std::time::Instant::now();
+            let drifted = rescan(&rescan_handle).await;
+            METRICS.teams_full_scan.observe(started.elapsed());
+            let mut data = rescan_handle.write().await;
+            prune_archives(&mut data);
+            data.watcher_stats.full_rescans += 1;
+            if drifted > 0 {
+                tracing::info!("full rescan found drift in {drifted} teams");
+                data.watcher_stats.full_rescans_with_drift += 1;
+                data.watcher_stats.drifted_teams += drifted as u64;
+                data.watcher_stats.last_drift_at = Some(now_ms());
+            }
         }
     });
 
+    HEALTH.set_watcher(WatcherState::Running);
+    while let Some(mut paths) = rx.recv().await {
+        tokio::time::sleep(DEBOUNCE_DURATION).await;
+        while let Ok(more) = rx.try_recv() {
+            paths.extend(more);
+        }
+        let started = std::time::Instant::now();
+        match affected_teams(&paths, &teams_dir, &tasks_dir) {
+            Some(teams) => {
+                for (name, changes) in &teams {
+                    rescan_team(&handle, name, *changes).await;
+                }
+                METRICS.teams_incremental_scan.observe(started.elapsed());
+                handle.write().await.watcher_stats.incremental_rescans += teams.len() as u64;
+            }
+            None => {
+                rescan(&handle).await;
+                METRICS.teams_full_scan.observe(started.elapsed());
+            }
+        }
+    }
+}
+
+/// Watches opencode's SQLite WAL and folds newly finished tool parts into the
+/// cached tool history of opencode-backed members, broadcasting each as a `TeamEvent`.
+async fn spawn_opencode_watcher(handle: TeamsHandle) {
+    let Some(db_path) = backends::opencode::opencode_db_path() else {
+        return;
+    };
+    let Some(db_dir) = db_path.parent().map(Path::to_path_buf) else {
+        return;
+    };
+    while !db_dir.exists() {
+        tokio::time::sleep(TEAMS_POLL_INTERVAL).await;
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<()>(16);
+    let db_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut watcher = match notify::recommended_watcher(move |res: Result<notify::Event, _>| {
+        if let Ok(event) = res {
+            // Writes land in opencode.db-wal first; the main file only
+            // changes on checkpoint. Match both.
+            let touches_db = event.paths.iter().any(|p| {
+                p.file_name()
+                    .map(|n| n.to_string_lossy().starts_with(&db_name))
+                    .unwrap_or(false)
+            });
+            if touches_db {
+                let _ = tx.try_send(());
+            }
+        }
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::warn!("failed to create opencode db watcher: {e}");
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&db_dir, RecursiveMode::NonRecursive) {
+        tracing::warn!("failed to watch opencode db dir: {e}");
+        return;
+    }
+
+    tracing::info!("watching opencode db at {}", db_path.display());
+    sync_opencode_tools(&handle).await;
+
+    // The interval also picks up members that joined since the last
+    // WAL write, which would otherwise wait for the next opencode activity.
+    let mut interval = tokio::time::interval(OPENCODE_DB_POLL_INTERVAL);
+    interval.tick().await;
     loop {
-        if rx.recv().await.is_none() {
-            break;
+        tokio::select! {
+            msg = rx.recv() => {
+                if msg.is_none() {
+                    break;
+                }
+                tokio::time::sleep(DEBOUNCE_DURATION).await;
+                while rx.try_recv().is_ok() {}
+            }
+            _ = interval.tick() => {}
         }
-        tokio::time::sleep(DEBOUNCE_DURATION).await;
-        while rx.try_recv().is_ok() {}
-        rescan(&handle).await;
+        sync_opencode_tools(&handle).await;
+    }
+}
+
+async fn sync_opencode_tools(handle: &TeamsHandle) {
+    let targets: Vec<(String, String, String, u64)> = {
+        let data = handle.read().await;
+        let mut v = Vec::new();
+        for (team_name, team) in &data.active {
+            for (member_name, member) in &team.members {
+                if member.backend_name() != "opencode" {
+                    continue;
+                }
+                let Some(session_id) = member.session.as_ref().and_then(|s| s.as_id()) else {
+                    continue;
+                };
+                let high_water = member.tool_cache.as_ref().map_or(0, |c| c.high_water);
+                v.push((
+                    team_name.clone(),
+                    member_name.clone(),
+                    session_id.to_string(),
+                    high_water,
+                ));
+            }
+        }
+        v
+    };
+
+    for (team_name, member_name, session_id, since) in targets {
+        let (new_calls, high_water) =
+            backends::opencode::read_opencode_tools_since(&session_id, since);
+
+        let mut data = handle.write().await;
+        let events = data.events.clone();
+        let Some(member) = data
+            .active
+            .get_mut(&team_name)
+            .and_then(|t| t.members.get_mut(&member_name))
+        else {
+            continue;
+        };
+        let initial_load = member.tool_cache.is_none();
+        let cache = member.tool_cache.get_or_insert_with(|| ToolCache {
+            calls: Vec::new(),
+            high_water: 0,
+        });
+        cache.high_water = high_water;
+        if new_calls.is_empty() {
+            continue;
+        }
+        for call in new_calls {
+            match cache.calls.iter_mut().find(|c| c.id == call.id) {
+                // Re-read from the high-water millisecond; nothing new to announce.
+                Some(existing) if *existing == call => continue,
+                Some(existing) => *existing = call.clone(),
+                None => cache.calls.push(call.clone()),
+            }
+            // Don't replay a member's whole history to subscribers on first load.
+            if !initial_load {
+                let _ = events.send(TeamEvent::ToolCall {
+                    team: team_name.clone(),
+                    member: member_name.clone(),
+                    call,
+                });
+            }
+        }
+        cache.calls.sort_by_key(|c| c.timestamp);
     }
 }
 
@@ -442,76 +1035,255 @@ async fn initial_scan(handle: &TeamsHandle) {
         if !config_path.exists() {
             continue;
         }
-        if let Some(state) = load_team_state(&team_name).await {
+        if let Some(state) = load_team_state(&team_name, data.archive_store.as_deref()).await {
             data.active.insert(team_name, state);
         }
     }
 
+    if let (Some(ref search), Some(ref store)) = (&data.search, &data.archive_store) {
+        let keys: Vec<&str> = data
+            .active
+            .keys()
+            .chain(data.archived.keys())
+            .map(String::as_str)
+            .collect();
+        search.retain_teams(&keys);
+        for id in data.archived.keys() {
+            if !search.has_team(id) {
+                search.replace_team(id, &archive_search_docs(&store.team_tools(id)));
+            }
+        }
+    }
+
     let active_count = data.active.len();
     let archived_count = data.archived.len();
     tracing::info!("initial scan: {active_count} active teams, {archived_count} archived");
 }
 
-async fn rescan(handle: &TeamsHandle) {
+/// Full pass over every team on disk and in memory. Returns how many teams changed,
+/// which should be zero if incremental rescans kept up.
+async fn rescan(handle: &TeamsHandle) -> usize {
     let teams_dir = teams_dir();
-    let current_dirs: Vec<String> = match std::fs::read_dir(&teams_dir) {
+    let mut names: BTreeSet<String> = match std::fs::read_dir(&teams_dir) {
         Ok(entries) => entries
             .flatten()
             .filter(|e| e.path().is_dir() && e.path().join("config.json").exists())
             .map(|e| e.file_name().to_string_lossy().to_string())
             .collect(),
-        Err(_) => Vec::new(),
+        Err(_) => BTreeSet::new(),
     };
+    names.extend(handle.read().await.active.keys().cloned());
 
-    let mut data = handle.write().await;
-
-    let removed: Vec<String> = data
-        .active
-        .keys()
-        .filter(|k| !current_dirs.contains(k))
-        .cloned()
-        .collect();
-    for name in removed {
-        tracing::info!("team '{name}' deleted, archiving");
-        if let Some(state) = data.active.remove(&name) {
-            let archive = archive_team(&state).await;
-            save_archive(&name, &archive);
-            data.archived.insert(name, archive);
+    let mut changed = 0;
+    for name in &names {
+        if rescan_team(handle, name, TeamChanges::ALL).await {
+            changed += 1;
         }
     }
+    changed
+}
 
-    drop(data);
+/// Bring one team in line with disk: load it if new, update the `changes` parts if
+/// present, archive it if its directory is gone. Returns whether anything changed.
+async fn rescan_team(handle: &TeamsHandle, name: &str, changes: TeamChanges) -> bool {
+    let exists = teams_dir().join(name).join("config.json").exists();
+    let (active, store) = {
+        let data = handle.read().await;
+        (data.active.contains_key(name), data.archive_store.clone())
+    };
+    match (exists, active) {
+        (true, true) => update_team_state(handle, name, changes).await,
+        (true, false) => match load_team_state(name, store.as_deref()).await {
+            // The event loop and the periodic rescan can both get here for a new team;
+            // the first one to finish loading wins.
+            Some(state) => match handle.write().await.active.entry(name.to_string()) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(slot) => {
+                    slot.insert(state);
+                    true
+                }
+            },
+            None => false,
+        },
+        (false, true) => archive_active_team(handle, name).await,
+        (false, false) => false,
+    }
+}
 
-    for name in &current_dirs {
-        let needs_update = {
-            let data = handle.read().await;
-            !data.active.contains_key(name)
+/// Moves a deleted team to the archive. The lock is only held to take the team out and
+/// to put the archive in: building it runs git and parses every transcript.
+async fn archive_active_team(handle: &TeamsHandle, name: &str) -> bool {
+    let (state, store, search) = {
+        let mut data = handle.write().await;
+        let Some(state) = data.active.remove(name) else {
+            return false;
         };
-        if needs_update {
-            if let Some(state) = load_team_state(name).await {
-                let mut data = handle.write().await;
-                data.active.insert(name.clone(), state);
+        (state, data.archive_store.clone(), data.search.clone())
+    };
+    tracing::info!("team '{name}' deleted, archiving");
+    let archive = archive_team(&state).await;
+    let id = archive.id();
+    // Final reindex while transcripts are still resolvable;
+    // archives only keep tool summaries.
+    if let Some(search) = search {
+        let members = MemberSnapshot::of_team(&state);
+        let (name, id) = (name.to_string(), id.clone());
+        tokio::task::spawn_blocking(move || {
+            search.replace_team(&name, &[]);
+            search.replace_team(&id, &team_search_docs(&members));
+        });
+    }
+    let archive = match store {
+        Some(store) => {
+            let (team, created_at) = (name.to_string(), state.config.created_at);
+            let saved = tokio::task::spawn_blocking(move || {
+                // The archive now carries each task's history; keep the log if it didn't land.
+                if store.save(&archive) {
+                    store.clear_task_events(&team, created_at);
+                }
+                archive
+            })
+            .await;
+            match saved {
+                Ok(archive) => archive,
+                Err(e) => {
+                    tracing::warn!("archiving team '{name}' failed: {e}");
+                    return true;
+                }
             }
-        } else {
-            update_team_state(handle, name).await;
+        }
+        None => archive,
+    };
+
+    let mut data = handle.write().await;
+    data.archived.insert(
+        id,
+        ArchivedTeam {
+            final_state: archive.final_state,
+            member_usage: archive.member_usage,
+        },
+    );
+    prune_archives(&mut data);
+    true
+}
+
+/// Drops archives past the retention policy. Runs on every archive and with the
+/// periodic full rescan, so age limits apply even when no team is being archived.
+fn prune_archives(data: &mut TeamsData) {
+    let Some(store) = data.archive_store.clone() else {
+        return;
+    };
+    for id in store.prune(&data.retention, now_ms()) {
+        tracing::info!("archive '{id}' removed by retention policy");
+        data.archived.remove(&id);
+        if let Some(ref search) = data.search {
+            search.replace_team(&id, &[]);
         }
     }
 }
 
-async fn update_team_state(handle: &TeamsHandle, team_name: &str) {
-    let teams_dir = teams_dir();
-    let config_path = teams_dir.join(team_name).join("config.json");
-    let config: TeamConfig = match read_json(&config_path) {
-        Some(c) => c,
-        None => return,
+/// Which parts of a team a batch of watcher events touched, so an update only
+/// reloads those. Member sessions are re-resolved only when the config changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct TeamChanges {
+    config: bool,
+    tasks: bool,
+    inboxes: bool,
+}
+
+impl TeamChanges {
+    const ALL: Self = Self {
+        config: true,
+        tasks: true,
+        inboxes: true,
     };
 
-    let tasks = load_tasks(team_name);
+    fn merge(&mut self, other: Self) {
+        self.config |= other.config;
+        self.tasks |= other.tasks;
+        self.inboxes |= other.inboxes;
+    }
+}
+
+/// Map watcher event paths to the teams they touch and what changed in each. `None`
+/// means an event hit the watched roots themselves and only a full rescan is safe.
+fn affected_teams(
+    paths: &[PathBuf],
+    teams_root: &Path,
+    tasks_root: &Path,
+) -> Option<BTreeMap<String, TeamChanges>> {
+    let mut teams: BTreeMap<String, TeamChanges> = BTreeMap::new();
+    for path in paths {
+        let (rel, in_tasks) = if let Ok(rel) = path.strip_prefix(teams_root) {
+            (rel, false)
+        } else if let Ok(rel) = path.strip_prefix(tasks_root) {
+            (rel, true)
+        } else {
+            continue;
+        };
+        let mut components = rel.components();
+        let Some(std::path::Component::Normal(name)) = components.next() else {
+            return None;
+        };
+        let changes = match (in_tasks, components.next()) {
+            (true, _) => TeamChanges {
+                tasks: true,
+                ..Default::default()
+            },
+            (false, Some(c)) if c.as_os_str() == "config.json" => TeamChanges {
+                config: true,
+                ..Default::default()
+            },
+            (false, Some(c)) if c.as_os_str() == "inboxes" => TeamChanges {
+                inboxes: true,
+                ..Default::default()
+            },
+            // The team dir itself appeared or went away.
+            _ => TeamChanges::ALL,
+        };
+        teams
+            .entry(name.to_string_lossy().to_string())
+            .or_default()
+            .merge(changes);
+    }
+    Some(teams)
+}
+
+/// Returns whether tasks or members changed.
+async fn update_team_state(handle: &TeamsHandle, team_name: &str, changes: TeamChanges) -> bool {
+    let config: Option<TeamConfig> = if changes.config {
+        let config_path = teams_dir().join(team_name).join("config.json");
+        match read_json(&config_path) {
+            Some(c) => Some(c),
+            None => return false,
+        }
+    } else {
+        None
+    };
+    let tasks = changes.tasks.then(|| load_tasks(team_name));
+    let inboxes = changes.inboxes.then(|| load_inboxes(team_name));
 
     let mut data = handle.write().await;
+    let store = data.archive_store.clone();
+    let mut changed = false;
     if let Some(team) = data.active.get_mut(team_name) {
-        team.config = config.clone();
-        team.tasks = tasks;
+        if let Some(ref config) = config {
+            team.config = config.clone();
+        }
+        if let Some(tasks) = tasks {
+            let observed = record_task_transitions(&mut team.task_history, &tasks, now_ms());
+            if let Some(ref store) = store {
+                store.append_task_events(team_name, team.config.created_at, &observed);
+            }
+            changed |= !observed.is_empty() || team.tasks.len() != tasks.len();
+            team.tasks = tasks;
+        }
+        if let Some(inboxes) = inboxes {
+            team.inboxes.extend(inboxes);
+        }
+        let Some(config) = config else {
+            return changed;
+        };
 
         let existing_names: Vec<String> = team.members.keys().cloned().collect();
         let new_names: Vec<String> = config.members.iter().map(|m| m.name.clone()).collect();
@@ -519,33 +1291,70 @@ async fn update_team_state(handle: &TeamsHandle, team_name: &str) {
         for name in &existing_names {
             if !new_names.contains(name) {
                 team.members.remove(name);
+                changed = true;
             }
         }
 
         for mc in &config.members {
             if !team.members.contains_key(&mc.name) {
+                changed = true;
                 let baseline = capture_baseline(&mc.cwd).await;
-                let session_path = resolve_session_path(mc);
+                let resolved = resolve_session(team_name, config.created_at, mc);
                 team.members.insert(
                     mc.name.clone(),
                     MemberState {
                         config: mc.clone(),
                         baseline_commit: baseline,
                         cached_summary: None,
-                        session_path,
+                        cached_usage: None,
+                        session_live: false,
+                        session: resolved.as_ref().map(|r| r.session.clone()),
+                        session_source: resolved.map(|r| r.method),
+                        session_len: 0,
+                        resolved_at: now_ms(),
+                        tool_cache: None,
                     },
                 );
             } else if let Some(ms) = team.members.get_mut(&mc.name) {
                 ms.config = mc.clone();
-                if ms.session_path.is_none() {
-                    ms.session_path = resolve_session_path(mc);
+                let stale = match (ms.backend(), &ms.session) {
+                    (Some(backend), Some(session)) => {
+                        session_gone_or_shrunk(session, &mut ms.session_len)
+                            || backend.session_superseded(
+                                &member_info(team_name, config.created_at, mc),
+                                session,
+                            )
+                    }
+                    (Some(_), None) => {
+                        now_ms().saturating_sub(ms.resolved_at)
+                            >= UNRESOLVED_RETRY_INTERVAL.as_millis() as u64
+                    }
+                    (None, _) => false,
+                };
+                if stale {
+                    ms.resolved_at = now_ms();
+                    if let Some(resolved) = resolve_session(team_name, config.created_at, mc) {
+                        if ms.session.as_ref() != Some(&resolved.session) {
+                            tracing::info!(
+                                "member '{}' session re-resolved via {}: {:?}",
+                                mc.name,
+                                resolved.method,
+                                resolved.session
+                            );
+                            ms.tool_cache = None;
+                            ms.session_len = 0;
+                        }
+                        ms.session = Some(resolved.session);
+                        ms.session_source = Some(resolved.method);
+                    }
                 }
             }
         }
     }
+    changed
 }
 
-async fn load_team_state(team_name: &str) -> Option<TeamState> {
+async fn load_team_state(team_name: &str, store: Option<&ArchiveStore>) -> Option<TeamState> {
     let teams_dir = teams_dir();
     let config_path = teams_dir.join(team_name).join("config.json");
     let config: TeamConfig = read_json(&config_path)?;
@@ -553,27 +1362,87 @@ async fn load_team_state(team_name: &str) -> Option<TeamState> {
     let mut members = HashMap::new();
     for mc in &config.members {
         let baseline = capture_baseline(&mc.cwd).await;
-        let session_path = resolve_session_path(mc);
+        let resolved = resolve_session(team_name, config.created_at, mc);
         members.insert(
             mc.name.clone(),
             MemberState {
                 config: mc.clone(),
                 baseline_commit: baseline,
                 cached_summary: None,
-                session_path,
+                cached_usage: None,
+                session_live: false,
+                session: resolved.as_ref().map(|r| r.session.clone()),
+                session_source: resolved.map(|r| r.method),
+                session_len: 0,
+                resolved_at: now_ms(),
+                tool_cache: None,
             },
         );
     }
 
     let tasks = load_tasks(team_name);
+    // Resume from the event log so a daemon restart doesn't reset
+    // every task's timeline to "first seen now".
+    let mut task_history = store
+        .map(|s| s.task_events(team_name, config.created_at))
+        .unwrap_or_default();
+    let observed = record_task_transitions(&mut task_history, &tasks, now_ms());
+    if let Some(store) = store {
+        store.append_task_events(team_name, config.created_at, &observed);
+    }
 
     Some(TeamState {
         config,
         members,
         tasks,
+        task_history,
+        inboxes: load_inboxes(team_name),
     })
 }
 
+/// Append a transition for every task whose status or owner changed. Returns the
+/// new transitions.
+fn record_task_transitions(
+    history: &mut HashMap<String, Vec<TaskTransition>>,
+    tasks: &HashMap<String, TaskFile>,
+    now: u64,
+) -> Vec<(String, TaskTransition)> {
+    let mut observed = Vec::new();
+    for (id, task) in tasks {
+        let entries = history.entry(id.clone()).or_default();
+        let changed = entries
+            .last()
+            .map(|last| last.status != task.status || last.owner != task.owner)
+            .unwrap_or(true);
+        if changed {
+            let transition = TaskTransition {
+                status: task.status.clone(),
+                owner: task.owner.clone(),
+                at: now,
+            };
+            entries.push(transition.clone());
+            observed.push((id.clone(), transition));
+        }
+    }
+    observed
+}
+
+fn load_inboxes(team_name: &str) -> HashMap<String, Vec<InboxMessage>> {
+    let dir = teams_dir().join(team_name).join("inboxes");
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+        .filter_map(|p| {
+            let member = p.file_stem()?.to_string_lossy().to_string();
+            Some((member, read_json::<Vec<InboxMessage>>(&p)?))
+        })
+        .collect()
+}
+
 fn load_tasks(team_name: &str) -> HashMap<String, TaskFile> {
     let tasks_dir = tasks_dir().join(team_name);
     let mut tasks = HashMap::new();
@@ -603,6 +1472,7 @@ fn load_tasks(team_name: &str) -> HashMap<String, TaskFile> {
 }
 
 async fn refresh_diff_summaries(handle: &TeamsHandle) {
+    let started = std::time::Instant::now();
     let members_to_refresh: Vec<(String, String, String, Option<String>)> = {
         let data = handle.read().await;
         let mut v = Vec::new();
@@ -630,6 +1500,133 @@ async fn refresh_diff_summaries(handle: &TeamsHandle) {
             }
         }
     }
+    METRICS.diff_refresh.observe(started.elapsed());
+}
+
+async fn refresh_session_stats(handle: &TeamsHandle) {
+    let sessions: Vec<_> = {
+        let data = handle.read().await;
+        data.active
+            .iter()
+            .flat_map(|(team_name, team)| {
+                team.members.iter().map(|(member_name, member)| {
+                    (
+                        team_name.clone(),
+                        member_name.clone(),
+                        member.backend(),
+                        member.session.clone(),
+                    )
+                })
+            })
+            .collect()
+    };
+    let stats: Vec<(String, String, TokenUsage, bool)> = sessions
+        .into_iter()
+        .map(|(team_name, member_name, backend, session)| {
+            let live = match (backend, &session) {
+                (Some(backend), Some(session)) => backend.is_live(session),
+                _ => false,
+            };
+            let usage = read_usage(backend, session.as_ref());
+            (team_name, member_name, usage, live)
+        })
+        .collect();
+
+    let mut data = handle.write().await;
+    for (team_name, member_name, usage, live) in stats {
+        if let Some(member) = data
+            .active
+            .get_mut(&team_name)
+            .and_then(|t| t.members.get_mut(&member_name))
+        {
+            member.cached_usage = Some(usage);
+            member.session_live = live;
+        }
+    }
+}
+
+async fn refresh_search_index(handle: &TeamsHandle) {
+    let (search, teams) = {
+        let data = handle.read().await;
+        let Some(search) = data.search.clone() else {
+            return;
+        };
+        let teams: Vec<(String, Vec<MemberSnapshot>)> = data
+            .active
+            .iter()
+            .map(|(name, team)| (name.clone(), MemberSnapshot::of_team(team)))
+            .collect();
+        (search, teams)
+    };
+
+    let names: Vec<String> = teams.iter().map(|(name, _)| name.clone()).collect();
+    let indexer = search.clone();
+    let indexed = tokio::task::spawn_blocking(move || {
+        for (team_name, members) in &teams {
+            indexer.replace_team(team_name, &team_search_docs(members));
+        }
+    })
+    .await;
+    if let Err(e) = indexed {
+        tracing::warn!("search index refresh failed: {e}");
+    }
+
+    // A team archived meanwhile already has its docs under the archive id.
+    let data = handle.read().await;
+    for name in names.iter().filter(|name| !data.active.contains_key(*name)) {
+        search.replace_team(name, &[]);
+    }
+}
+
+/// A member's session, copied out of `TeamsData` so transcripts are read without the lock.
+struct MemberSnapshot {
+    name: String,
+    backend: Option<&'static dyn AgentBackend>,
+    session: Option<SessionRef>,
+    cached_tools: Option<Vec<ToolCall>>,
+}
+
+impl MemberSnapshot {
+    fn of_team(team: &TeamState) -> Vec<Self> {
+        team.members
+            .iter()
+            .map(|(name, member)| Self {
+                name: name.clone(),
+                backend: member.backend(),
+                session: member.session.clone(),
+                cached_tools: member.tool_cache.as_ref().map(|c| c.calls.clone()),
+            })
+            .collect()
+    }
+}
+
+fn team_search_docs(members: &[MemberSnapshot]) -> Vec<SearchDoc> {
+    let mut docs = Vec::new();
+    for member in members {
+        let name = &member.name;
+        let (Some(backend), Some(session)) = (member.backend, &member.session) else {
+            continue;
+        };
+        let tools = match member.cached_tools {
+            Some(ref calls) => calls.clone(),
+            None => backend.read_tools(session),
+        };
+        docs.extend(tools.iter().map(|c| SearchDoc::from_tool_call(name, c)));
+        docs.extend(
+            backend
+                .read_transcript(session)
+                .iter()
+                .filter_map(|m| SearchDoc::from_transcript(name, m)),
+        );
+    }
+    docs
+}
+
+fn archive_search_docs(member_tools: &HashMap<String, Vec<ToolCall>>) -> Vec<SearchDoc> {
+    member_tools
+        .iter()
+        .flat_map(|(name, calls)| calls.iter().map(|c| SearchDoc::from_tool_call(name, c)))
+        .collect()
 }
 
 // --- Git operations ---
@@ -761,35 +1758,65 @@ fn parse_name_status(output: &str) -> HashMap<String, String> {
 
 // --- Session resolution ---
 
-fn resolve_session_path(member: &MemberConfig) -> Option<PathBuf> {
-    let backend = member.backend_type.as_deref().unwrap_or("claude");
-    if backend != "claude" {
+fn resolve_session(
+    team_name: &str,
+    team_created_at: u64,
+    member: &MemberConfig,
+) -> Option<ResolvedSession> {
+    let backend_name = member
+        .backend_type
+        .as_deref()
+        .unwrap_or(backends::DEFAULT_BACKEND);
+    let Some(backend) = backends::get(backend_name) else {
+        tracing::warn!(
+            "member '{}' uses unknown backend '{backend_name}', tool history unavailable",
+            member.name
+        );
         return None;
-    }
-    let pane_id = member.tmux_pane_id.as_deref()?;
-    let active_sessions_path = claude_dir().join("active-sessions.json");
-    let sessions: ActiveSessions = read_json(&active_sessions_path)?;
+    };
+    backend.resolve_session(&member_info(team_name, team_created_at, member))
+}
 
-    for session in sessions.sessions.values() {
-        if let Some(ref tmux) = session.tmux {
-            if tmux.pane_id == pane_id {
-                let path = PathBuf::from(&session.transcript_path);
-                if path.exists() {
-                    return Some(path);
-                }
-            }
-        }
+fn member_info<'a>(
+    team_name: &'a str,
+    team_created_at: u64,
+    member: &'a MemberConfig,
+) -> MemberInfo<'a> {
+    MemberInfo {
+        team_name,
+        name: &member.name,
+        agent_id: &member.agent_id,
+        cwd: &member.cwd,
+        spawned_at: member.joined_at.unwrap_or(team_created_at),
+        tmux_pane_id: member.tmux_pane_id.as_deref(),
+        opencode_session_id: member.opencode_session_id.as_deref(),
     }
-    None
+}
+
+/// True if a file-backed session was deleted or truncated since the last check.
+fn session_gone_or_shrunk(session: &SessionRef, seen_len: &mut u64) -> bool {
+    let Some(path) = session.as_path() else {
+        return false;
+    };
+    let Ok(meta) = std::fs::metadata(path) else {
+        return true;
+    };
+    let shrunk = meta.len() < *seen_len;
+    *seen_len = meta.len();
+    shrunk
 }
 
 // --- Archival ---
 
 async fn archive_team(team: &TeamState) -> TeamArchive {
-    let summary = build_team_summary(team, true);
+    let archived_at = now_ms();
+    let mut summary = build_team_summary(team, true);
+    summary.archived_at = Some(archived_at);
+    summary.archive_id = Some(crate::archive::archive_id(&team.config.name, archived_at));
 
     let mut member_diffs = HashMap::new();
     let mut member_tools = HashMap::new();
+    let mut member_usage = HashMap::new();
 
     for (name, member) in &team.members {
         if let Some(ref baseline) = member.baseline_commit {
@@ -798,66 +1825,60 @@ async fn archive_team(team: &TeamState) -> TeamArchive {
             }
         }
 
-        let backend = member.config.backend_type.as_deref().unwrap_or("claude");
-        let calls = read_member_tools(member, backend);
+        let calls = read_member_tools(member);
         if !calls.is_empty() {
             member_tools.insert(name.clone(), calls);
         }
-    }
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
+        let usage = read_member_usage(member);
+        if usage != TokenUsage::default() {
+            member_usage.insert(name.clone(), usage);
+        }
+    }
 
     TeamArchive {
         name: team.config.name.clone(),
-        archived_at: now,
+        archived_at,
+        tasks: snapshot_tasks(team),
+        inboxes: team.inboxes.clone(),
         final_state: summary,
         member_diffs,
         member_tools,
+        member_usage,
     }
 }
 
-fn save_archive(name: &str, archive: &TeamArchive) {
-    let dir = archive_dir();
-    if let Err(e) = std::fs::create_dir_all(&dir) {
-        tracing::warn!("failed to create archive dir: {e}");
-        return;
-    }
-    let path = dir.join(format!("{name}.json"));
-    match serde_json::to_string_pretty(archive) {
-        Ok(json) => {
-            if let Err(e) = std::fs::write(&path, json) {
-                tracing::warn!("failed to write archive {}: {e}", path.display());
-            }
-        }
-        Err(e) => tracing::warn!("failed to serialize archive: {e}"),
-    }
+fn snapshot_tasks(team: &TeamState) -> Vec<ArchivedTask> {
+    let mut tasks: Vec<ArchivedTask> = team
+        .tasks
+        .iter()
+        .map(|(id, t)| ArchivedTask {
+            id: id.clone(),
+            subject: t.subject.clone(),
+            description: t.description.clone(),
+            status: t.status.clone(),
+            owner: t.owner.clone(),
+            blocks: t.blocks.clone(),
+            blocked_by: t.blocked_by.clone(),
+            history: team.task_history.get(id).cloned().unwrap_or_default(),
+        })
+        .collect();
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    tasks
 }
 
-fn load_archives() -> HashMap<String, TeamArchive> {
-    let dir = archive_dir();
-    let mut archives = HashMap::new();
-    if !dir.exists() {
-        return archives;
-    }
-    let entries = match std::fs::read_dir(&dir) {
-        Ok(e) => e,
-        Err(_) => return archives,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().map(|e| e == "json").unwrap_or(false) {
-            if let Some(archive) = read_json::<TeamArchive>(&path) {
-                archives.insert(archive.name.clone(), archive);
-            }
-        }
-    }
-    archives
+// --- Helpers ---
+
+fn to_pretty_json<T: Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
 }
 
-// --- Helpers ---
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
 fn build_team_summary(team: &TeamState, archived: bool) -> TeamSummary {
     let members: Vec<MemberSummary> = team
@@ -871,6 +1892,9 @@ fn build_team_summary(team: &TeamState, archived: bool) -> TeamSummary {
             is_active: m.config.is_active.unwrap_or(false),
             color: m.config.color.clone(),
             diff_summary: m.cached_summary.clone(),
+            usage: m.cached_usage.clone(),
+            session_live: m.session_live,
+            session_source: m.session_source.map(String::from),
         })
         .collect();
 
@@ -899,6 +1923,8 @@ fn build_team_summary(team: &TeamState, archived: bool) -> TeamSummary {
         description: team.config.description.clone(),
         created_at: team.config.created_at,
         archived,
+        archived_at: None,
+        archive_id: None,
         members,
         tasks,
         conflicts,
@@ -929,7 +1955,7 @@ fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
     serde_json::from_str(&contents).ok()
 }
 
-fn claude_dir() -> PathBuf {
+pub fn claude_dir() -> PathBuf {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
     PathBuf::from(home).join(".claude")
 }
@@ -942,11 +1968,6 @@ fn tasks_dir() -> PathBuf {
     claude_dir().join("tasks")
 }
 
-fn archive_dir() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
-    PathBuf::from(home).join(".nightshift/team-archives")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -979,6 +2000,203 @@ mod tests {
         assert_eq!(map.get("src/renamed.rs").unwrap(), "modified");
     }
 
+    fn team(name: &str, created_at: u64, archived_at: Option<u64>) -> TeamSummary {
+        TeamSummary {
+            name: name.to_string(),
+            description: String::new(),
+            created_at,
+            archived: archived_at.is_some(),
+            archived_at,
+            archive_id: archived_at.map(|at| crate::archive::archive_id(name, at)),
+            members: Vec::new(),
+            tasks: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn should_filter_sort_and_page_teams() {
+        let teams = || {
+            vec![
+                team("old", 1, Some(10)),
+                team("live", 5, None),
+                team("recent", 2, Some(20)),
+            ]
+        };
+        let names = |teams: Vec<TeamSummary>| teams.into_iter().map(|t| t.name).collect::<Vec<_>>();
+
+        let (page, total) = page_teams(teams(), &TeamListQuery::default());
+        assert_eq!(total, 3);
+        assert_eq!(names(page), vec!["live", "recent", "old"]);
+
+        let archived = TeamListQuery {
+            archived: Some(true),
+            ascending: true,
+            ..Default::default()
+        };
+        let (page, total) = page_teams(teams(), &archived);
+        assert_eq!(total, 2);
+        assert_eq!(names(page), vec!["old", "recent"]);
+
+        let paged = TeamListQuery {
+            offset: 1,
+            limit: Some(1),
+            ..Default::default()
+        };
+        let (page, total) = page_teams(teams(), &paged);
+        assert_eq!(total, 3);
+        assert_eq!(names(page), vec!["recent"]);
+    }
+
+    #[test]
+    fn should_resolve_team_keys_unambiguously() {
+        let archived = |name: &str, at: u64| ArchivedTeam {
+            final_state: team(name, 0, Some(at)),
+            member_usage: HashMap::new(),
+        };
+        let (events, _) = tokio::sync::broadcast::channel(1);
+        let mut data = TeamsData {
+            active: HashMap::new(),
+            archived: HashMap::from([
+                ("frontend@1".to_string(), archived("frontend", 1)),
+                ("frontend@2".to_string(), archived("frontend", 2)),
+            ]),
+            archive_store: None,
+            retention: RetentionPolicy::default(),
+            events,
+            search: None,
+            watcher_stats: WatcherStats::default(),
+        };
+
+        assert_eq!(
+            data.resolve("frontend"),
+            Some(TeamKey::Archived("frontend@2".into()))
+        );
+        assert_eq!(
+            data.resolve("frontend@1"),
+            Some(TeamKey::Archived("frontend@1".into()))
+        );
+
+        let config: TeamConfig =
+            serde_json::from_str(r#"{"name":"frontend","members":[]}"#).unwrap();
+        data.active.insert(
+            "frontend".into(),
+            TeamState {
+                config,
+                members: HashMap::new(),
+                tasks: HashMap::new(),
+                task_history: HashMap::new(),
+                inboxes: HashMap::new(),
+            },
+        );
+        assert_eq!(
+            data.resolve("frontend"),
+            Some(TeamKey::Active("frontend".into()))
+        );
+        assert_eq!(
+            data.resolve("frontend@2"),
+            Some(TeamKey::Archived("frontend@2".into()))
+        );
+        assert_eq!(data.resolve("backend"), None);
+    }
+
+    #[test]
+    fn should_record_only_status_and_owner_changes() {
+        let task = |status: &str, owner: Option<&str>| -> TaskFile {
+            serde_json::from_value(serde_json::json!({
+                "id": 1, "subject": "s", "status": status, "owner": owner
+            }))
+            .unwrap()
+        };
+        let mut history = HashMap::new();
+        let snapshot = |t: TaskFile| HashMap::from([("1".to_string(), t)]);
+
+        record_task_transitions(&mut history, &snapshot(task("pending", None)), 1);
+        record_task_transitions(&mut history, &snapshot(task("pending", None)), 2);
+        record_task_transitions(&mut history, &snapshot(task("pending", Some("w1"))), 3);
+        record_task_transitions(&mut history, &snapshot(task("completed", Some("w1"))), 4);
+
+        let at: Vec<u64> = history["1"].iter().map(|t| t.at).collect();
+        assert_eq!(at, vec![1, 3, 4]);
+        assert_eq!(history["1"][2].status, "completed");
+    }
+
+    #[test]
+    fn should_deserialize_inbox_messages() {
+        let json = r#"[
+            {"from": "team-lead", "text": "start on #2", "timestamp": "2026-01-01T00:00:00Z", "read": true},
+            {"from": "w1", "text": "done", "color": "blue"}
+        ]"#;
+        let messages: Vec<InboxMessage> = serde_json::from_str(json).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].read);
+        assert_eq!(messages[1].text, "done");
+    }
+
+    #[test]
+    fn should_route_event_paths_to_teams() {
+        let teams = PathBuf::from("/home/u/.claude/teams");
+        let tasks = PathBuf::from("/home/u/.claude/tasks");
+
+        let affected = affected_teams(
+            &[
+                teams.join("alpha/config.json"),
+                teams.join("alpha/inboxes/w1.json"),
+                tasks.join("beta/3.json"),
+                teams.join("gamma"),
+                PathBuf::from("/elsewhere/file"),
+            ],
+            &teams,
+            &tasks,
+        )
+        .unwrap();
+        assert_eq!(
+            affected.keys().collect::<Vec<_>>(),
+            vec!["alpha", "beta", "gamma"]
+        );
+        assert_eq!(
+            affected["alpha"],
+            TeamChanges {
+                config: true,
+                tasks: false,
+                inboxes: true,
+            }
+        );
+        assert_eq!(
+            affected["beta"],
+            TeamChanges {
+                tasks: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(affected["gamma"], TeamChanges::ALL);
+
+        assert!(affected_teams(std::slice::from_ref(&teams), &teams, &tasks).is_none());
+    }
+
+    #[test]
+    fn should_detect_deleted_or_truncated_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, "{}\n{}\n").unwrap();
+        let session = SessionRef::Path(path.clone());
+        let mut seen_len = 0;
+        assert!(!session_gone_or_shrunk(&session, &mut seen_len));
+        assert_eq!(seen_len, 6);
+
+        std::fs::write(&path, "{}\n{}\n{}\n").unwrap();
+        assert!(!session_gone_or_shrunk(&session, &mut seen_len));
+        std::fs::write(&path, "{}\n").unwrap();
+        assert!(session_gone_or_shrunk(&session, &mut seen_len));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(session_gone_or_shrunk(&session, &mut seen_len));
+        assert!(!session_gone_or_shrunk(
+            &SessionRef::Id("ses_1".into()),
+            &mut seen_len
+        ));
+    }
+
     #[test]
     fn should_detect_conflicts() {
         let members = vec![
@@ -989,6 +2207,9 @@ mod tests {
                 cwd: String::new(),
                 is_active: true,
                 color: None,
+                usage: None,
+                session_live: false,
+                session_source: None,
                 diff_summary: Some(DiffSummary {
                     files_changed: 1,
                     additions: 5,
@@ -1008,6 +2229,9 @@ mod tests {
                 cwd: String::new(),
                 is_active: true,
                 color: None,
+                usage: None,
+                session_live: false,
+                session_source: None,
                 diff_summary: Some(DiffSummary {
                     files_changed: 1,
                     additions: 3,
@@ -1037,6 +2261,9 @@ mod tests {
                 cwd: String::new(),
                 is_active: true,
                 color: None,
+                usage: None,
+                session_live: false,
+                session_source: None,
                 diff_summary: Some(DiffSummary {
                     files_changed: 1,
                     additions: 1,
@@ -1056,6 +2283,9 @@ mod tests {
                 cwd: String::new(),
                 is_active: true,
                 color: None,
+                usage: None,
+                session_live: false,
+                session_source: None,
                 diff_summary: Some(DiffSummary {
                     files_changed: 1,
                     additions: 1,
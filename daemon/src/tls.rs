@@ -0,0 +1,373 @@
+//! TLS termination for the proxy's TCP listener. Certificates come from `tls.certPath`
+//! and `tls.keyPath` in `config.json`; when neither file exists a self-signed ECDSA P-256
+//! pair is generated there on first run. The certificate's SHA-256 fingerprint is
+//! reported in the node registration so the server can pin it.
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Handshakes in flight at once; further connections wait in the kernel backlog.
+const MAX_CONCURRENT_HANDSHAKES: usize = 256;
+const CERT_VALIDITY_DAYS: i64 = 3650;
+const CERT_COMMON_NAME: &str = "nightshift-daemon";
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM certificate chain. Defaults to `~/.nightshift/tls/cert.pem`.
+    pub cert_path: Option<PathBuf>,
+    /// PEM private key. Defaults to `~/.nightshift/tls/key.pem`.
+    pub key_path: Option<PathBuf>,
+}
+
+pub struct TlsMaterial {
+    pub server_config: Arc<ServerConfig>,
+    /// Uppercase, colon-separated SHA-256 of the leaf certificate (openssl's format).
+    pub fingerprint: String,
+}
+
+fn tls_dir() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".nightshift").join("tls")
+}
+
+/// Loads the configured certificate, generating a self-signed one covering `hosts`
+/// (plus localhost) if neither the cert nor the key exists yet.
+pub fn load_or_generate(config: &TlsConfig, hosts: &[String]) -> Result<TlsMaterial> {
+    let cert_path = config
+        .cert_path
+        .clone()
+        .unwrap_or_else(|| tls_dir().join("cert.pem"));
+    let key_path = config
+        .key_path
+        .clone()
+        .unwrap_or_else(|| tls_dir().join("key.pem"));
+
+    match (cert_path.exists(), key_path.exists()) {
+        (true, true) => {}
+        (false, false) => {
+            let mut names = vec!["localhost".to_string(), "127.0.0.1".into(), "::1".into()];
+            for host in hosts {
+                if !names.contains(host) {
+                    names.push(host.clone());
+                }
+            }
+            let (cert, key) = self_signed(&names)?;
+            write_pem(&cert_path, "CERTIFICATE", &cert, false)?;
+            write_pem(&key_path, "PRIVATE KEY", &key, true)?;
+            tracing::info!(
+                "generated self-signed certificate at {} for {}",
+                cert_path.display(),
+                names.join(", ")
+            );
+        }
+        (true, false) => return Err(anyhow!("tls key missing: {}", key_path.display())),
+        (false, true) => return Err(anyhow!("tls cert missing: {}", cert_path.display())),
+    }
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow!("failed to read {}: {e}", cert_path.display()))?;
+    let key = PrivateKeyDer::from_pem_file(&key_path)
+        .map_err(|e| anyhow!("failed to read {}: {e}", key_path.display()))?;
+    server_material(certs, key)
+}
+
+fn server_material(
+    certs: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+) -> Result<TlsMaterial> {
+    let leaf = certs.first().context("tls certificate chain is empty")?;
+    let fingerprint = fingerprint(leaf);
+
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let mut server_config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .context("failed to select tls protocol versions")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("tls certificate and key do not match")?;
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsMaterial {
+        server_config: Arc::new(server_config),
+        fingerprint,
+    })
+}
+
+pub fn fingerprint(cert: &CertificateDer<'_>) -> String {
+    ring::digest::digest(&ring::digest::SHA256, cert.as_ref())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn write_pem(path: &Path, label: &str, der: &[u8], private: bool) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let encoded = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // Created owner-only rather than chmod-ed after the key is already on disk.
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    std::io::Write::write_all(&mut file, pem.as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+// --- Self-signed certificate ---
+
+/// Returns `(certificate DER, PKCS#8 key DER)`.
+fn self_signed(names: &[String]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let key_pair = rcgen::KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256)
+        .context("failed to generate tls key")?;
+    let mut params =
+        rcgen::CertificateParams::new(names.to_vec()).context("invalid certificate host name")?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, CERT_COMMON_NAME);
+    let now = time::OffsetDateTime::now_utc();
+    params.not_before = now - time::Duration::days(1);
+    params.not_after = now + time::Duration::days(CERT_VALIDITY_DAYS);
+    let cert = params
+        .self_signed(&key_pair)
+        .context("failed to sign certificate")?;
+    Ok((cert.der().to_vec(), key_pair.serialize_der()))
+}
+
+// --- Listener ---
+
+/// Accepts TCP connections and completes TLS handshakes off the accept path, so one
+/// slow client cannot stall everyone else. Handshakes are bounded in time and number,
+/// so clients that connect and never finish can't pile up tasks and sockets.
+pub struct TlsListener {
+    rx: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: Arc<ServerConfig>) -> std::io::Result<Self> {
+        Self::with_limits(
+            listener,
+            config,
+            MAX_CONCURRENT_HANDSHAKES,
+            HANDSHAKE_TIMEOUT,
+        )
+    }
+
+    fn with_limits(
+        listener: TcpListener,
+        config: Arc<ServerConfig>,
+        max_handshakes: usize,
+        handshake_timeout: Duration,
+    ) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(config);
+        let handshakes = Arc::new(Semaphore::new(max_handshakes));
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                let permit = tokio::select! {
+                    _ = tx.closed() => break,
+                    permit = Arc::clone(&handshakes).acquire_owned() => match permit {
+                        Ok(permit) => permit,
+                        Err(_) => break,
+                    },
+                };
+                let (stream, addr) = tokio::select! {
+                    _ = tx.closed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            tracing::warn!("tls listener accept failed: {e}");
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            continue;
+                        }
+                    },
+                };
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let handshake =
+                        tokio::time::timeout(handshake_timeout, acceptor.accept(stream)).await;
+                    drop(permit);
+                    match handshake {
+                        Ok(Ok(tls)) => {
+                            let _ = tx.send((tls, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!("tls handshake from {addr} failed: {e}"),
+                        Err(_) => tracing::debug!("tls handshake from {addr} timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self { rx, local_addr })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.rx.recv().await {
+            Some(accepted) => accepted,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::TlsConnector;
+
+    #[test]
+    fn should_generate_once_and_reuse_the_pair() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TlsConfig {
+            cert_path: Some(dir.path().join("tls/cert.pem")),
+            key_path: Some(dir.path().join("tls/key.pem")),
+        };
+        let first = load_or_generate(&config, &["node.example.com".into()]).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join("tls/key.pem"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let second = load_or_generate(&config, &[]).unwrap();
+        assert_eq!(first.fingerprint, second.fingerprint);
+        assert_eq!(first.fingerprint.len(), 32 * 3 - 1);
+
+        std::fs::remove_file(dir.path().join("tls/key.pem")).unwrap();
+        assert!(load_or_generate(&config, &[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_complete_a_handshake_against_the_self_signed_cert() {
+        let (cert, key) = self_signed(&["localhost".into(), "127.0.0.1".into()]).unwrap();
+        let cert = CertificateDer::from(cert);
+        let material =
+            server_material(vec![cert.clone()], PrivateKeyDer::Pkcs8(key.into())).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut tls_listener = TlsListener::new(listener, material.server_config).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = axum::serve::Listener::accept(&mut tls_listener).await;
+            stream.write_all(b"pong").await.unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+        let client_config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = TlsConnector::from(Arc::new(client_config));
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let mut stream = connector
+            .connect("localhost".try_into().unwrap(), tcp)
+            .await
+            .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        assert_eq!(reply, "pong");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_time_out_stalled_handshakes_that_hold_the_only_slot() {
+        let (cert, key) = self_signed(&["localhost".into()]).unwrap();
+        let cert = CertificateDer::from(cert);
+        let material =
+            server_material(vec![cert.clone()], PrivateKeyDer::Pkcs8(key.into())).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut tls_listener = TlsListener::with_limits(
+            listener,
+            material.server_config,
+            1,
+            Duration::from_millis(300),
+        )
+        .unwrap();
+
+        // Connects and never says hello, taking the single handshake slot.
+        let _stalled = TcpStream::connect(addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+        let client_config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = TlsConnector::from(Arc::new(client_config));
+        let started = std::time::Instant::now();
+        let client = tokio::spawn(async move {
+            let tcp = TcpStream::connect(addr).await.unwrap();
+            connector
+                .connect("localhost".try_into().unwrap(), tcp)
+                .await
+                .unwrap()
+        });
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            axum::serve::Listener::accept(&mut tls_listener),
+        )
+        .await
+        .expect("the stalled handshake should time out and free its slot");
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        client.await.unwrap();
+    }
+}
@@ -1,16 +1,41 @@
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+/// Upper bound on tool output returned by the detail endpoint. Bash and Read
+/// results can run to megabytes; the UI only needs enough to debug a call.
+pub const MAX_TOOL_OUTPUT_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolCall {
+    #[serde(default)]
+    pub id: String,
     pub tool: String,
     pub title: Option<String>,
     pub input_summary: String,
     pub status: String,
     pub timestamp: u64,
     pub duration_ms: Option<u64>,
+    /// Full input as plain text for the search index. Clients get the input
+    /// from the detail endpoint instead.
+    #[serde(skip)]
+    pub input_text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCallDetail {
+    pub id: String,
+    pub tool: String,
+    pub title: Option<String>,
+    pub input: serde_json::Value,
+    pub output: Option<String>,
+    pub output_truncated: bool,
+    pub error: Option<String>,
+    pub status: String,
+    pub timestamp: u64,
+    pub duration_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -27,31 +52,174 @@ pub struct MemberToolHistory {
 #[serde(rename_all = "camelCase")]
 pub struct ToolStats {
     pub total: u32,
-    pub reads: u32,
-    pub writes: u32,
-    pub edits: u32,
-    pub bash: u32,
-    pub other: u32,
+    pub errors: u32,
+    pub error_rate: f64,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    /// Keyed by lowercased tool name so Claude's `Bash` and OpenCode's `bash` share a bucket.
+    pub by_tool: BTreeMap<String, ToolBreakdown>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolBreakdown {
+    pub count: u32,
+    pub errors: u32,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
 }
 
 impl ToolStats {
     pub fn from_calls(calls: &[ToolCall]) -> Self {
         let mut stats = Self::default();
+        let mut all_durations = Vec::new();
+        let mut tool_durations: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+
         for call in calls {
+            let key = call.tool.to_lowercase();
+            let is_error = call.status == "error";
             stats.total += 1;
-            match call.tool.to_lowercase().as_str() {
-                "read" => stats.reads += 1,
-                "write" => stats.writes += 1,
-                "edit" => stats.edits += 1,
-                "bash" => stats.bash += 1,
-                _ => stats.other += 1,
+            let entry = stats.by_tool.entry(key.clone()).or_default();
+            entry.count += 1;
+            if is_error {
+                stats.errors += 1;
+                entry.errors += 1;
+            }
+            if let Some(d) = call.duration_ms {
+                all_durations.push(d);
+                tool_durations.entry(key).or_default().push(d);
             }
         }
+
+        for (tool, mut durations) in tool_durations {
+            durations.sort_unstable();
+            if let Some(entry) = stats.by_tool.get_mut(&tool) {
+                entry.p50_ms = percentile(&durations, 50.0);
+                entry.p95_ms = percentile(&durations, 95.0);
+                entry.max_ms = durations.last().copied();
+            }
+        }
+
+        all_durations.sort_unstable();
+        stats.p50_ms = percentile(&all_durations, 50.0);
+        stats.p95_ms = percentile(&all_durations, 95.0);
+        stats.max_ms = all_durations.last().copied();
+        if stats.total > 0 {
+            stats.error_rate = stats.errors as f64 / stats.total as f64;
+        }
         stats
     }
 }
 
-fn summarize_input(tool: &str, input: &serde_json::Value) -> String {
+/// Nearest-rank percentile over an ascending slice.
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberTranscript {
+    pub name: String,
+    pub team: String,
+    pub backend: String,
+    pub messages: Vec<TranscriptMessage>,
+    /// Pass back as `cursor` to fetch the next page; absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMessage {
+    pub id: String,
+    pub role: String,
+    pub timestamp: u64,
+    pub blocks: Vec<TranscriptBlock>,
+}
+
+/// One piece of a message. `ToolUse.id` and `ToolResult.toolUseId` match
+/// `ToolCall.id`, so clients can fetch full input/output from the tools endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptBlock {
+    Text {
+        text: String,
+    },
+    Reasoning {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        tool: String,
+    },
+    ToolResult {
+        #[serde(rename = "toolUseId")]
+        tool_use_id: String,
+        #[serde(rename = "isError")]
+        is_error: bool,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_write_tokens: u64,
+    /// Estimated from list prices for Claude transcripts; reported by opencode otherwise.
+    pub cost_usd: f64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// USD per million tokens: (input, output, cache write, cache read).
+fn model_pricing(model: &str) -> Option<(f64, f64, f64, f64)> {
+    let model = model.to_lowercase();
+    if model.contains("opus-4-5") || model.contains("opus-4-6") {
+        Some((5.0, 25.0, 6.25, 0.50))
+    } else if model.contains("opus") {
+        Some((15.0, 75.0, 18.75, 1.50))
+    } else if model.contains("sonnet") {
+        Some((3.0, 15.0, 3.75, 0.30))
+    } else if model.contains("haiku-4-5") {
+        Some((1.0, 5.0, 1.25, 0.10))
+    } else if model.contains("haiku") {
+        Some((0.80, 4.0, 1.0, 0.08))
+    } else if model.contains("gpt-5-mini") {
+        Some((0.25, 2.0, 0.0, 0.025))
+    } else if model.contains("gpt-5") {
+        Some((1.25, 10.0, 0.0, 0.125))
+    } else {
+        None
+    }
+}
+
+pub fn estimate_cost(model: &str, usage: &TokenUsage) -> f64 {
+    let Some((input, output, cache_write, cache_read)) = model_pricing(model) else {
+        return 0.0;
+    };
+    (usage.input_tokens as f64 * input
+        + usage.output_tokens as f64 * output
+        + usage.cache_write_tokens as f64 * cache_write
+        + usage.cache_read_tokens as f64 * cache_read)
+        / 1_000_000.0
+}
+
+pub fn summarize_input(tool: &str, input: &serde_json::Value) -> String {
     match tool.to_lowercase().as_str() {
         "read" => input
             .get("file_path")
@@ -102,252 +270,41 @@ fn summarize_input(tool: &str, input: &serde_json::Value) -> String {
     }
 }
 
-// --- OpenCode SQLite reader ---
-
-pub fn read_opencode_tools(session_id: &str) -> Vec<ToolCall> {
-    let db_path = opencode_db_path();
-    let Some(db_path) = db_path else {
-        return Vec::new();
-    };
-    if !db_path.exists() {
-        return Vec::new();
-    }
-
-    let conn = match rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    ) {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::warn!("failed to open opencode db: {e}");
-            return Vec::new();
-        }
-    };
-
-    let mut stmt = match conn.prepare(
-        "SELECT data, time_created FROM part \
-         WHERE session_id = ?1 \
-         AND json_extract(data, '$.type') = 'tool' \
-         AND json_extract(data, '$.state.status') IN ('completed', 'error') \
-         ORDER BY time_created",
-    ) {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::warn!("failed to prepare opencode query: {e}");
-            return Vec::new();
-        }
-    };
-
-    let rows = match stmt.query_map([session_id], |row| {
-        let data: String = row.get(0)?;
-        let time_created: u64 = row.get(1)?;
-        Ok((data, time_created))
-    }) {
-        Ok(r) => r,
-        Err(e) => {
-            tracing::warn!("failed to query opencode tools: {e}");
-            return Vec::new();
-        }
-    };
-
-    let mut calls = Vec::new();
-    for row in rows {
-        let Ok((data, time_created)) = row else {
-            continue;
-        };
-        if let Some(call) = parse_opencode_part(&data, time_created) {
-            calls.push(call);
-        }
-    }
-    calls
-}
-
-#[derive(Deserialize)]
-struct OpenCodePart {
-    tool: Option<String>,
-    state: Option<OpenCodeToolState>,
-}
-
-#[derive(Deserialize)]
-struct OpenCodeToolState {
-    status: String,
-    input: Option<serde_json::Value>,
-    title: Option<String>,
-    time: Option<OpenCodeToolTime>,
-    error: Option<String>,
-}
-
-#[derive(Deserialize)]
-struct OpenCodeToolTime {
-    start: Option<u64>,
-    end: Option<u64>,
-}
-
-fn parse_opencode_part(data: &str, time_created: u64) -> Option<ToolCall> {
-    let part: OpenCodePart = serde_json::from_str(data).ok()?;
-    let tool = part.tool?;
-    let state = part.state?;
-    let input = state
-        .input
-        .as_ref()
-        .cloned()
-        .unwrap_or(serde_json::Value::Null);
-    let summary = summarize_input(&tool, &input);
-
-    let duration_ms = state.time.as_ref().and_then(|t| {
-        let start = t.start?;
-        let end = t.end?;
-        Some(end.saturating_sub(start))
-    });
-
-    let timestamp = state
-        .time
-        .as_ref()
-        .and_then(|t| t.start)
-        .unwrap_or(time_created);
-
-    let title = if state.status == "error" {
-        state.error.or(state.title)
-    } else {
-        state.title
-    };
-
-    Some(ToolCall {
-        tool,
-        title,
-        input_summary: summary,
-        status: state.status,
-        timestamp,
-        duration_ms,
-    })
-}
-
-fn opencode_db_path() -> Option<std::path::PathBuf> {
-    let home = std::env::var("HOME").ok()?;
-    let path = if cfg!(target_os = "macos") {
-        std::path::PathBuf::from(&home).join("Library/Application Support/opencode/opencode.db")
-    } else {
-        let xdg_data =
-            std::env::var("XDG_DATA_HOME").unwrap_or_else(|_| format!("{home}/.local/share"));
-        std::path::PathBuf::from(xdg_data).join("opencode/opencode.db")
-    };
-    Some(path)
-}
-
-// --- Claude Code JSONL reader ---
-
-pub fn read_claude_tools(transcript_path: &Path) -> Vec<ToolCall> {
-    if !transcript_path.exists() {
-        return Vec::new();
-    }
-
-    let contents = match std::fs::read_to_string(transcript_path) {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::warn!(
-                "failed to read claude transcript {}: {e}",
-                transcript_path.display()
-            );
-            return Vec::new();
-        }
-    };
-
-    let mut tool_uses: Vec<PendingToolUse> = Vec::new();
-    let mut calls = Vec::new();
-
-    for line in contents.lines() {
-        if line.is_empty() {
-            continue;
-        }
-        let entry: serde_json::Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        let msg_type = entry.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        let timestamp = entry
-            .get("timestamp")
-            .and_then(|v| v.as_str())
-            .and_then(parse_iso_timestamp)
-            .unwrap_or(0);
-
-        match msg_type {
-            "assistant" => {
-                let content = entry.pointer("/message/content").and_then(|v| v.as_array());
-                if let Some(blocks) = content {
-                    for block in blocks {
-                        if block.get("type").and_then(|v| v.as_str()) == Some("tool_use") {
-                            let id = block.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                            let name = block.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                            let input = block
-                                .get("input")
-                                .cloned()
-                                .unwrap_or(serde_json::Value::Null);
-                            tool_uses.push(PendingToolUse {
-                                id: id.to_string(),
-                                tool: name.to_string(),
-                                input,
-                                timestamp,
-                            });
-                        }
-                    }
-                }
+/// Every string and number in a tool input, one per line: the whole command,
+/// pattern or patch rather than `summarize_input`'s one-liner. Capped like output.
+pub fn input_text(input: &serde_json::Value) -> String {
+    fn collect(value: &serde_json::Value, out: &mut String) {
+        match value {
+            serde_json::Value::String(s) => {
+                out.push_str(s);
+                out.push('\n');
             }
-            "user" => {
-                let content = entry.pointer("/message/content").and_then(|v| v.as_array());
-                if let Some(blocks) = content {
-                    for block in blocks {
-                        if block.get("type").and_then(|v| v.as_str()) == Some("tool_result") {
-                            let tool_use_id = block
-                                .get("tool_use_id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("");
-                            let is_error = block
-                                .get("is_error")
-                                .and_then(|v| v.as_bool())
-                                .unwrap_or(false);
-
-                            if let Some(pos) = tool_uses.iter().position(|t| t.id == tool_use_id) {
-                                let pending = tool_uses.remove(pos);
-                                let summary = summarize_input(&pending.tool, &pending.input);
-                                let duration_ms = if timestamp > pending.timestamp {
-                                    Some(timestamp - pending.timestamp)
-                                } else {
-                                    None
-                                };
-                                calls.push(ToolCall {
-                                    tool: pending.tool,
-                                    title: None,
-                                    input_summary: summary,
-                                    status: if is_error {
-                                        "error".to_string()
-                                    } else {
-                                        "completed".to_string()
-                                    },
-                                    timestamp: pending.timestamp,
-                                    duration_ms,
-                                });
-                            }
-                        }
-                    }
-                }
+            serde_json::Value::Number(n) => {
+                out.push_str(&n.to_string());
+                out.push('\n');
             }
-            _ => {}
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect(v, out)),
+            serde_json::Value::Object(fields) => fields.values().for_each(|v| collect(v, out)),
+            serde_json::Value::Bool(_) | serde_json::Value::Null => {}
         }
     }
-
-    // NOTE(victor): Pending tool_uses without results are still in-flight -- skip them
-    calls
+    let mut out = String::new();
+    collect(input, &mut out);
+    truncate_output(out).0
 }
 
-struct PendingToolUse {
-    id: String,
-    tool: String,
-    input: serde_json::Value,
-    timestamp: u64,
+pub fn truncate_output(output: String) -> (String, bool) {
+    if output.len() <= MAX_TOOL_OUTPUT_BYTES {
+        return (output, false);
+    }
+    let mut end = MAX_TOOL_OUTPUT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    (output[..end].to_string(), true)
 }
 
-fn parse_iso_timestamp(s: &str) -> Option<u64> {
+pub fn parse_iso_timestamp(s: &str) -> Option<u64> {
     // NOTE(victor): Timestamps are ISO 8601 like "2026-02-20T19:39:14.770Z".
     // Parse to epoch ms without pulling in chrono -- just use time crate already in deps.
     let format = time::format_description::well_known::Rfc3339;
@@ -394,56 +351,49 @@ mod tests {
         assert_eq!(summarize_input("Grep", &input), "fn main");
     }
 
+    fn call(tool: &str, status: &str, duration_ms: Option<u64>) -> ToolCall {
+        ToolCall {
+            id: String::new(),
+            tool: tool.into(),
+            title: None,
+            input_summary: "f".into(),
+            status: status.into(),
+            timestamp: 0,
+            duration_ms,
+            input_text: String::new(),
+        }
+    }
+
     #[test]
     fn should_compute_tool_stats() {
         let calls = vec![
-            ToolCall {
-                tool: "Read".into(),
-                title: None,
-                input_summary: "f".into(),
-                status: "completed".into(),
-                timestamp: 0,
-                duration_ms: None,
-            },
-            ToolCall {
-                tool: "Read".into(),
-                title: None,
-                input_summary: "f".into(),
-                status: "completed".into(),
-                timestamp: 0,
-                duration_ms: None,
-            },
-            ToolCall {
-                tool: "Edit".into(),
-                title: None,
-                input_summary: "f".into(),
-                status: "completed".into(),
-                timestamp: 0,
-                duration_ms: None,
-            },
-            ToolCall {
-                tool: "Bash".into(),
-                title: None,
-                input_summary: "f".into(),
-                status: "completed".into(),
-                timestamp: 0,
-                duration_ms: None,
-            },
-            ToolCall {
-                tool: "Glob".into(),
-                title: None,
-                input_summary: "f".into(),
-                status: "completed".into(),
-                timestamp: 0,
-                duration_ms: None,
-            },
+            call("Read", "completed", Some(10)),
+            call("read", "completed", Some(30)),
+            call("Edit", "completed", None),
+            call("Bash", "error", Some(500)),
+            call("mcp__github__create_pr", "completed", Some(1200)),
         ];
         let stats = ToolStats::from_calls(&calls);
         assert_eq!(stats.total, 5);
-        assert_eq!(stats.reads, 2);
-        assert_eq!(stats.edits, 1);
-        assert_eq!(stats.bash, 1);
-        assert_eq!(stats.other, 1);
+        assert_eq!(stats.errors, 1);
+        assert!((stats.error_rate - 0.2).abs() < f64::EPSILON);
+        assert_eq!(stats.by_tool["read"].count, 2);
+        assert_eq!(stats.by_tool["read"].p50_ms, Some(10));
+        assert_eq!(stats.by_tool["read"].max_ms, Some(30));
+        assert_eq!(stats.by_tool["edit"].count, 1);
+        assert_eq!(stats.by_tool["edit"].p50_ms, None);
+        assert_eq!(stats.by_tool["bash"].errors, 1);
+        assert_eq!(stats.by_tool["mcp__github__create_pr"].count, 1);
+        assert_eq!(stats.max_ms, Some(1200));
+    }
+
+    #[test]
+    fn should_compute_nearest_rank_percentiles() {
+        let durations: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&durations, 50.0), Some(50));
+        assert_eq!(percentile(&durations, 95.0), Some(95));
+        assert_eq!(percentile(&[7], 95.0), Some(7));
+        assert_eq!(percentile(&[], 50.0), None);
     }
 
     #[test]
@@ -455,33 +405,9 @@ mod tests {
     }
 
     #[test]
-    fn should_parse_opencode_tool_part() {
-        let data = r#"{"type":"tool","tool":"edit","callID":"call_1","state":{"status":"completed","input":{"file_path":"/src/main.rs"},"output":"ok","title":"Edit main.rs","time":{"start":1000,"end":1250}}}"#;
-        let call = parse_opencode_part(data, 1000).unwrap();
-        assert_eq!(call.tool, "edit");
-        assert_eq!(call.title, Some("Edit main.rs".to_string()));
-        assert_eq!(call.input_summary, "/src/main.rs");
-        assert_eq!(call.status, "completed");
-        assert_eq!(call.duration_ms, Some(250));
-    }
-
-    #[test]
-    fn should_read_claude_tools_from_jsonl() {
-        let jsonl = concat!(
-            r#"{"type":"assistant","timestamp":"2026-02-20T19:39:14.000Z","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}]},"uuid":"a1"}"#,
-            "\n",
-            r#"{"type":"user","timestamp":"2026-02-20T19:39:15.000Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"file1\nfile2"}]},"uuid":"a2"}"#,
-            "\n",
-        );
-        let dir = tempfile::tempdir().unwrap();
-        let path = dir.path().join("test.jsonl");
-        std::fs::write(&path, jsonl).unwrap();
-
-        let calls = read_claude_tools(&path);
-        assert_eq!(calls.len(), 1);
-        assert_eq!(calls[0].tool, "Bash");
-        assert_eq!(calls[0].input_summary, "ls");
-        assert_eq!(calls[0].status, "completed");
-        assert_eq!(calls[0].duration_ms, Some(1000));
+    fn should_truncate_large_tool_output() {
+        let (out, truncated) = truncate_output("é".repeat(MAX_TOOL_OUTPUT_BYTES));
+        assert!(truncated);
+        assert!(out.len() <= MAX_TOOL_OUTPUT_BYTES);
     }
 }
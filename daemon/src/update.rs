@@ -12,9 +12,12 @@ const BIN_NAME: &str = "nightshift-daemon";
 /// Every async project using self_update does this (Dioxus, feroxbuster,
 /// aliyundrive-webdav, moonup).
 pub async fn check_and_apply() -> Result<bool> {
-    tokio::task::spawn_blocking(do_update)
+    let result = tokio::task::spawn_blocking(do_update)
         .await
-        .context("update task panicked")?
+        .context("update task panicked")
+        .and_then(|r| r);
+    crate::health::HEALTH.record_update_check(result.as_ref().err().map(|e| format!("{e:#}")));
+    result
 }
 
 fn do_update() -> Result<bool> {
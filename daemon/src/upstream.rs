@@ -0,0 +1,333 @@
+//! Keep-alive connection pool for forwarding requests to opencode.
+//!
+//! Connections go back to the pool once hyper reports them ready again, i.e. after the
+//! previous response body has been fully read. Upgrade requests always get a dedicated
+//! connection, since the socket is handed over to the websocket pipe afterwards.
+//!
+//! Hop-by-hop headers are dropped before a request goes out: a client's `Connection: close`
+//! describes its own socket to the daemon, not the pooled one to opencode.
+
+use anyhow::{Context, Result};
+use axum::body::Body;
+use hyper::body::Incoming;
+use hyper::client::conn::http1::SendRequest;
+use hyper::header::{HeaderName, HeaderValue, CONNECTION, TE, TRAILER, UPGRADE};
+use hyper::HeaderMap;
+use hyper_util::rt::TokioIo;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+use crate::metrics::METRICS;
+
+const STARTUP_RETRY_WINDOW: Duration = Duration::from_secs(8);
+const STARTUP_MAX_RETRIES: u32 = 5;
+const STARTUP_RETRY_DELAY: Duration = Duration::from_millis(200);
+const MAX_IDLE: usize = 32;
+// Bun.serve closes keep-alive sockets after 10s idle by default. Retire
+// ours well before that so we never write a request into a socket it is closing.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct IdleConn {
+    sender: SendRequest<Body>,
+    since: Instant,
+}
+
+pub struct UpstreamPool {
+    port: u16,
+    start_time: Instant,
+    idle: Mutex<Vec<IdleConn>>,
+}
+
+impl UpstreamPool {
+    pub fn new(port: u16, start_time: Instant) -> Arc<Self> {
+        Arc::new(Self {
+            port,
+            start_time,
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub async fn send(
+        self: &Arc<Self>,
+        mut req: hyper::Request<Body>,
+    ) -> Result<hyper::Response<Incoming>> {
+        let is_upgrade = req.headers().contains_key(UPGRADE);
+        strip_hop_by_hop(req.headers_mut(), is_upgrade);
+        if is_upgrade {
+            let mut sender = self.connect().await?;
+            return Ok(sender.send_request(req).await?);
+        }
+
+        if let Some(mut sender) = self.checkout() {
+            match sender.try_send_request(req).await {
+                Ok(resp) => {
+                    self.checkin_when_idle(sender);
+                    return Ok(resp);
+                }
+                // The request never hit the wire, so it is safe to replay on a new socket.
+                Err(mut e) => match e.take_message() {
+                    Some(unsent) => {
+                        tracing::debug!("pooled upstream connection unusable: {}", e.error());
+                        req = unsent;
+                    }
+                    None => return Err(e.into_error()).context("upstream request failed"),
+                },
+            }
+        }
+
+        let mut sender = self.connect().await?;
+        let resp = sender.send_request(req).await?;
+        self.checkin_when_idle(sender);
+        Ok(resp)
+    }
+
+    fn checkout(&self) -> Option<SendRequest<Body>> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(conn) = idle.pop() {
+            if conn.since.elapsed() < IDLE_TIMEOUT
+                && !conn.sender.is_closed()
+                && conn.sender.is_ready()
+            {
+                return Some(conn.sender);
+            }
+        }
+        None
+    }
+
+    fn checkin_when_idle(self: &Arc<Self>, mut sender: SendRequest<Body>) {
+        let pool = Arc::clone(self);
+        tokio::spawn(async move {
+            if sender.ready().await.is_err() {
+                return;
+            }
+            let mut idle = pool.idle.lock().unwrap_or_else(|e| e.into_inner());
+            idle.retain(|c| c.since.elapsed() < IDLE_TIMEOUT && !c.sender.is_closed());
+            if idle.len() < MAX_IDLE {
+                idle.push(IdleConn {
+                    sender,
+                    since: Instant::now(),
+                });
+            }
+        });
+    }
+
+    async fn connect(&self) -> Result<SendRequest<Body>> {
+        let stream = {
+            let mut attempts = 0u32;
+            loop {
+                match TcpStream::connect(("127.0.0.1", self.port)).await {
+                    Ok(s) => break s,
+                    Err(e)
+                        if e.kind() == std::io::ErrorKind::ConnectionRefused
+                            && attempts < STARTUP_MAX_RETRIES
+                            && self.start_time.elapsed() < STARTUP_RETRY_WINDOW =>
+                    {
+                        attempts += 1;
+                        METRICS
+                            .upstream_connect_retries
+                            .fetch_add(1, Ordering::Relaxed);
+                        tracing::debug!(
+                            "opencode not ready yet (attempt {}), retrying in {:?}",
+                            attempts,
+                            STARTUP_RETRY_DELAY
+                        );
+                        tokio::time::sleep(STARTUP_RETRY_DELAY).await;
+                    }
+                    Err(e) => return Err(e).context("opencode unreachable"),
+                }
+            }
+        };
+
+        METRICS
+            .upstream_connections_opened
+            .fetch_add(1, Ordering::Relaxed);
+        let io = TokioIo::new(stream);
+        let (sender, conn) = hyper::client::conn::http1::handshake(io).await?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.with_upgrades().await {
+                tracing::debug!("upstream connection ended: {e}");
+            }
+        });
+        Ok(sender)
+    }
+}
+
+/// Removes headers that only apply to one connection (RFC 9110 section 7.6.1), including any
+/// named in `Connection`. With `keep_upgrade` the `Upgrade` handshake headers survive.
+pub fn strip_hop_by_hop(headers: &mut HeaderMap, keep_upgrade: bool) {
+    let mut hop: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    hop.extend(
+        headers
+            .keys()
+            .filter(|name| name.as_str().starts_with("proxy-"))
+            .cloned(),
+    );
+    hop.extend([
+        CONNECTION,
+        HeaderName::from_static("keep-alive"),
+        TE,
+        TRAILER,
+        UPGRADE,
+    ]);
+    for name in hop {
+        if keep_upgrade && name == UPGRADE {
+            continue;
+        }
+        headers.remove(name);
+    }
+    if keep_upgrade {
+        headers.insert(CONNECTION, HeaderValue::from_static("upgrade"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal HTTP/1.1 server answering `ok` to every request. With `close_after`
+    /// set it drops each connection after that many responses without saying so.
+    async fn serve_ok(close_after: Option<usize>) -> (u16, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut served = 0;
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    loop {
+                        while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                            match stream.read(&mut chunk).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                            }
+                        }
+                        buf.clear();
+                        let resp = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+                        if stream.write_all(resp).await.is_err() {
+                            return;
+                        }
+                        served += 1;
+                        if close_after == Some(served) {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        (port, accepted)
+    }
+
+    async fn get(pool: &Arc<UpstreamPool>) -> String {
+        let req = hyper::Request::get("/").body(Body::empty()).unwrap();
+        let resp = pool.send(req).await.unwrap();
+        let body = axum::body::to_bytes(Body::new(resp.into_body()), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    /// Lets the check-in task observe the finished response.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn should_reuse_idle_connections() {
+        let (port, accepted) = serve_ok(None).await;
+        let pool = UpstreamPool::new(port, Instant::now());
+        for _ in 0..5 {
+            assert_eq!(get(&pool).await, "ok");
+            settle().await;
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_replace_connections_closed_by_upstream() {
+        let (port, accepted) = serve_ok(Some(1)).await;
+        let pool = UpstreamPool::new(port, Instant::now());
+        for _ in 0..3 {
+            assert_eq!(get(&pool).await, "ok");
+            settle().await;
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn should_strip_hop_by_hop_headers() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("connection", "close, x-trace"),
+            ("keep-alive", "timeout=5"),
+            ("te", "trailers"),
+            ("trailer", "expires"),
+            ("proxy-authorization", "Basic Zm9v"),
+            ("proxy-connection", "keep-alive"),
+            ("x-trace", "1"),
+            ("upgrade", "websocket"),
+            ("authorization", "Bearer t"),
+            ("content-type", "application/json"),
+        ] {
+            headers.insert(name, value.parse().unwrap());
+        }
+
+        let mut plain = headers.clone();
+        strip_hop_by_hop(&mut plain, false);
+        let mut names: Vec<_> = plain.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["authorization", "content-type"]);
+
+        strip_hop_by_hop(&mut headers, true);
+        assert_eq!(headers["connection"], "upgrade");
+        assert_eq!(headers["upgrade"], "websocket");
+        assert!(!headers.contains_key("x-trace"));
+        assert!(!headers.contains_key("keep-alive"));
+    }
+
+    #[tokio::test]
+    async fn should_forward_closing_clients_over_pooled_connections() {
+        let (port, accepted) = serve_ok(None).await;
+        let pool = UpstreamPool::new(port, Instant::now());
+        for _ in 0..3 {
+            let req = hyper::Request::get("/")
+                .header(CONNECTION, "close")
+                .body(Body::empty())
+                .unwrap();
+            let resp = pool.send(req).await.unwrap();
+            axum::body::to_bytes(Body::new(resp.into_body()), usize::MAX)
+                .await
+                .unwrap();
+            settle().await;
+        }
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_not_pool_connections_with_unread_bodies() {
+        let (port, accepted) = serve_ok(None).await;
+        let pool = UpstreamPool::new(port, Instant::now());
+        let held = pool
+            .send(hyper::Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(get(&pool).await, "ok");
+        drop(held);
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,9 +1,10 @@
 //! Fake opencode binary for integration tests.
-//! Listens on the requested port, writes PID to a file if FAKE_OPENCODE_PID_FILE is set,
-//! and exits on SIGTERM (default behavior).
+//! Listens on the requested port and answers every HTTP request with `200 {}` on keep-alive
+//! connections (after `<ms>` for `/delay/<ms>`), writes PID
+//! to a file if FAKE_OPENCODE_PID_FILE is set, and exits on SIGTERM (default behavior).
 
-use std::net::TcpListener;
-use std::time::Duration;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -17,11 +18,42 @@ fn main() {
         let _ = std::fs::write(&path, std::process::id().to_string());
     }
 
-    let _listener =
+    let listener =
         TcpListener::bind(("127.0.0.1", port)).expect("fake_opencode: failed to bind port");
 
-    // Sleep until killed (SIGTERM/SIGKILL from daemon).
+    // Serve until killed (SIGTERM/SIGKILL from daemon).
+    for stream in listener.incoming().flatten() {
+        std::thread::spawn(move || respond(stream));
+    }
+}
+
+fn respond(mut stream: TcpStream) {
+    let mut chunk = [0u8; 1024];
     loop {
-        std::thread::sleep(Duration::from_secs(1));
+        let mut request = Vec::new();
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => request.extend_from_slice(&chunk[..n]),
+            }
+        }
+        let head = String::from_utf8_lossy(&request).to_ascii_lowercase();
+        let closing = head.contains("\r\nconnection: close");
+        let delay_ms = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|path| path.strip_prefix("/delay/"))
+            .and_then(|ms| ms.parse().ok());
+        if let Some(ms) = delay_ms {
+            std::thread::sleep(std::time::Duration::from_millis(ms));
+        }
+        let head: &[u8] = if closing {
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n"
+        } else {
+            b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\n\r\n"
+        };
+        if stream.write_all(head).is_err() || stream.write_all(b"{}").is_err() || closing {
+            return;
+        }
     }
 }
@@ -0,0 +1,130 @@
+//! Integration tests for SIGTERM/SIGINT handling: drain, deregister, stop opencode.
+
+#[path = "support/mod.rs"]
+mod support;
+
+#[cfg(unix)]
+mod tests {
+    use super::support::*;
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    use serial_test::serial;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Raw response of a `Connection: close` GET.
+    fn http_get(port: u16, path: &str) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes())?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    fn shuts_down_cleanly_on(signal: Signal) {
+        kill_stale_port_holders(19276);
+        kill_stale_port_holders(19277);
+        let home = TestHome::new();
+        let opencode_pid_file = home.path.join("opencode_child.pid");
+
+        let mut daemon = spawn_daemon(
+            &home,
+            &[(
+                "FAKE_OPENCODE_PID_FILE",
+                opencode_pid_file.to_str().unwrap(),
+            )],
+        );
+
+        assert!(
+            wait_for_port(19277, Duration::from_secs(15)),
+            "proxy port never came up"
+        );
+        let opencode_pid =
+            read_pid_file(&opencode_pid_file).expect("opencode pid file not written");
+
+        // Still waiting on opencode when the signal lands; the drain must let it finish.
+        let in_flight = thread::spawn(|| http_get(19277, "/delay/1500"));
+        thread::sleep(Duration::from_millis(300));
+
+        kill(Pid::from_raw(daemon.id() as i32), signal).unwrap();
+
+        let response = in_flight.join().unwrap().expect("in-flight request failed");
+        assert!(
+            response.starts_with("HTTP/1.1 200") && response.ends_with("{}"),
+            "in-flight request cut off by {signal}: {response:?}"
+        );
+
+        assert!(
+            wait_for_child_exit(&mut daemon, Duration::from_secs(20)),
+            "daemon did not exit within 20s of {signal}"
+        );
+        let status = daemon.wait().unwrap();
+        assert!(status.success(), "daemon exited with {status} on {signal}");
+
+        assert!(
+            wait_for_pid_exit(opencode_pid, Duration::from_secs(5)),
+            "opencode pid {opencode_pid} still alive after shutdown"
+        );
+        assert!(
+            !home.pid_file().exists(),
+            "opencode pid file left behind after shutdown"
+        );
+        let nodes =
+            std::fs::read_to_string(home.nightshift_dir().join("nodes.json")).unwrap_or_default();
+        assert!(
+            !nodes.contains("\"id\""),
+            "node still registered after shutdown: {nodes}"
+        );
+
+        wait_for_port_free(19276, Duration::from_secs(5));
+        wait_for_port_free(19277, Duration::from_secs(5));
+    }
+
+    #[test]
+    #[serial]
+    fn sigterm_drains_and_stops_opencode() {
+        shuts_down_cleanly_on(Signal::SIGTERM);
+    }
+
+    #[test]
+    #[serial]
+    fn sigint_drains_and_stops_opencode() {
+        shuts_down_cleanly_on(Signal::SIGINT);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[serial]
+    fn sigkill_takes_opencode_down_too() {
+        kill_stale_port_holders(19276);
+        kill_stale_port_holders(19277);
+        let home = TestHome::new();
+        let opencode_pid_file = home.path.join("opencode_child.pid");
+        let mut daemon = spawn_daemon(
+            &home,
+            &[(
+                "FAKE_OPENCODE_PID_FILE",
+                opencode_pid_file.to_str().unwrap(),
+            )],
+        );
+        assert!(
+            wait_for_port(19277, Duration::from_secs(15)),
+            "proxy port never came up"
+        );
+        let opencode_pid =
+            read_pid_file(&opencode_pid_file).expect("opencode pid file not written");
+
+        kill_and_wait(&mut daemon);
+
+        assert!(
+            wait_for_pid_exit(opencode_pid, Duration::from_secs(5)),
+            "opencode pid {opencode_pid} outlived a SIGKILLed daemon"
+        );
+        wait_for_port_free(19276, Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,111 @@
+//! Integration tests for `GET /health` and `GET /ready` against the fake opencode.
+
+#[path = "support/mod.rs"]
+mod support;
+
+#[cfg(unix)]
+mod tests {
+    use super::support::*;
+    use serial_test::serial;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    /// Status code and body of a `Connection: close` GET.
+    fn http_get(port: u16, path: &str) -> (u16, String) {
+        http_get_as(port, path, None)
+    }
+
+    fn http_get_as(port: u16, path: &str, token: Option<&str>) -> (u16, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let auth = token
+            .map(|t| format!("Authorization: Bearer {t}\r\n"))
+            .unwrap_or_default();
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\n{auth}Connection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        let text = String::from_utf8_lossy(&buf).into_owned();
+        let status = text
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|s| s.parse().ok())
+            .expect("status line");
+        let body = text
+            .split_once("\r\n\r\n")
+            .map(|(_, b)| b.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    #[test]
+    #[serial]
+    fn health_and_ready_report_daemon_state() {
+        kill_stale_port_holders(19276);
+        kill_stale_port_holders(19277);
+        let home = TestHome::new();
+        let mut daemon = spawn_daemon(&home, &[]);
+
+        assert!(
+            wait_for_port(19277, Duration::from_secs(15)),
+            "proxy port never came up"
+        );
+
+        let (status, body) = http_get(19277, "/health");
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""status":"ok""#), "unexpected body: {body}");
+
+        let (status, body) = http_get(19277, "/ready");
+        assert_eq!(status, 200, "not ready: {body}");
+        let ready: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(ready["ready"], true);
+        assert_eq!(ready["opencode"]["answering"], true);
+        assert_eq!(ready["watchdogGeneration"], 0);
+        assert_eq!(ready["update"]["enabled"], false);
+
+        kill_and_wait(&mut daemon);
+        wait_for_port_free(19276, Duration::from_secs(5));
+        wait_for_port_free(19277, Duration::from_secs(5));
+    }
+
+    #[test]
+    #[serial]
+    fn ready_hides_details_from_unauthenticated_callers() {
+        kill_stale_port_holders(19276);
+        kill_stale_port_holders(19277);
+        let home = TestHome::new();
+        write_test_config(&home, 19277);
+        let config_path = home.nightshift_dir().join("config.json");
+        let mut config: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        config["auth"] = serde_json::json!({ "tokens": ["probe-token"] });
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        let mut daemon = spawn_daemon(&home, &[]);
+
+        assert!(
+            wait_for_port(19277, Duration::from_secs(15)),
+            "proxy port never came up"
+        );
+
+        let (status, body) = http_get(19277, "/ready");
+        assert_eq!(status, 200, "not ready: {body}");
+        let ready: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(ready, serde_json::json!({ "ready": true }));
+
+        let (status, body) = http_get_as(19277, "/ready", Some("probe-token"));
+        assert_eq!(status, 200, "not ready: {body}");
+        let ready: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(ready["opencode"]["answering"], true);
+
+        assert_eq!(http_get(19277, "/teams").0, 401);
+
+        kill_and_wait(&mut daemon);
+        wait_for_port_free(19276, Duration::from_secs(5));
+        wait_for_port_free(19277, Duration::from_secs(5));
+    }
+}
@@ -9,9 +9,10 @@ mod tests {
     use std::net::TcpStream;
     use std::sync::{Arc, Mutex};
     use std::thread;
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     const TEST_PROXY_PORT: u16 = 19377;
+    const OPENCODE_PORT: u16 = 19276;
 
     fn http_get_status(port: u16, path: &str) -> Result<u16, String> {
         let mut stream = TcpStream::connect(("127.0.0.1", port)).map_err(|e| e.to_string())?;
@@ -40,6 +41,113 @@ mod tests {
         Ok(status)
     }
 
+    /// Mean latency of `rounds` sequential GETs, or the first non-200.
+    fn mean_latency(port: u16, path: &str, rounds: u32) -> Result<Duration, String> {
+        let start = Instant::now();
+        for _ in 0..rounds {
+            match http_get_status(port, path)? {
+                200 => {}
+                code => return Err(format!("{path} returned status {code}")),
+            }
+        }
+        Ok(start.elapsed() / rounds)
+    }
+
+    fn upstream_connections_opened(port: u16) -> u64 {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut text = String::new();
+        stream.read_to_string(&mut text).unwrap();
+        text.lines()
+            .find_map(|line| line.strip_prefix("nightshift_upstream_connections_opened_total "))
+            .and_then(|v| v.trim().parse().ok())
+            .expect("upstream connection counter in /metrics")
+    }
+
+    #[test]
+    #[serial]
+    fn proxy_reuses_upstream_connections_for_closing_clients() {
+        kill_stale_port_holders(OPENCODE_PORT);
+        kill_stale_port_holders(19277);
+
+        let home = TestHome::new();
+        let mut daemon = spawn_daemon(&home, &[]);
+        assert!(
+            wait_for_port(19277, Duration::from_secs(15)),
+            "proxy port never came up"
+        );
+        assert_eq!(http_get_status(19277, "/session"), Ok(200));
+        thread::sleep(Duration::from_millis(100));
+
+        let before = upstream_connections_opened(19277);
+        for _ in 0..10 {
+            let status = http_get_status(19277, "/session");
+            if status != Ok(200) {
+                kill_and_wait(&mut daemon);
+                panic!("proxied request failed: {status:?}");
+            }
+            // Give the pool a moment to take the finished connection back.
+            thread::sleep(Duration::from_millis(50));
+        }
+        let opened = upstream_connections_opened(19277) - before;
+        kill_and_wait(&mut daemon);
+
+        assert_eq!(
+            opened, 0,
+            "each `Connection: close` client request opened a new upstream connection"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn real_opencode_proxy_forwarding_benchmark() {
+        if !has_real_opencode() {
+            eprintln!("skipping: real opencode not found on PATH");
+            return;
+        }
+
+        kill_stale_port_holders(OPENCODE_PORT);
+        kill_stale_port_holders(TEST_PROXY_PORT);
+
+        let home = TestHome::new();
+        write_test_config(&home, TEST_PROXY_PORT);
+        let mut daemon = spawn_daemon_real_opencode(&home, &[]);
+
+        assert!(
+            wait_for_port(TEST_PROXY_PORT, Duration::from_secs(40)),
+            "proxy port never came up"
+        );
+
+        let rounds = 200;
+        let mut report = Vec::new();
+        for path in ["/path", "/session", "/config"] {
+            let warmup = mean_latency(TEST_PROXY_PORT, path, 5);
+            let direct = mean_latency(OPENCODE_PORT, path, rounds);
+            let proxied = mean_latency(TEST_PROXY_PORT, path, rounds);
+            match (warmup, direct, proxied) {
+                (Ok(_), Ok(direct), Ok(proxied)) => report.push(format!(
+                    "{path}: direct {direct:?}, proxied {proxied:?}, overhead {:?}",
+                    proxied.saturating_sub(direct)
+                )),
+                (w, d, p) => {
+                    kill_and_wait(&mut daemon);
+                    panic!("benchmark request failed: {w:?} {d:?} {p:?}");
+                }
+            }
+        }
+
+        kill_and_wait(&mut daemon);
+        eprintln!("proxy forwarding benchmark ({rounds} sequential requests each):");
+        for line in report {
+            eprintln!("  {line}");
+        }
+    }
+
     #[test]
     #[serial]
     fn real_opencode_parallel_blast_endpoints_succeed() {
@@ -48,7 +156,7 @@ mod tests {
             return;
         }
 
-        kill_stale_port_holders(19276);
+        kill_stale_port_holders(OPENCODE_PORT);
         kill_stale_port_holders(TEST_PROXY_PORT);
 
         let home = TestHome::new();
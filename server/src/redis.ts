@@ -11,6 +11,7 @@ export interface Node {
   arch: string;
   daemonVersion: string;
   machineName?: string;
+  tlsFingerprint?: string;
 }
 
 const redis = new RedisClient(process.env.REDIS_URL);
@@ -96,27 +96,39 @@ function MemberToolsPanel({ team, name }: { team: string; name: string }) {
     <div className="space-y-3">
       <div className="flex flex-wrap gap-2 text-xs font-mono">
         <span className="text-muted-fg">{data.stats.total} total</span>
-        {data.stats.reads > 0 && (
-          <span className="text-blue-400">{data.stats.reads} reads</span>
-        )}
-        {data.stats.writes > 0 && (
-          <span className="text-green-400">{data.stats.writes} writes</span>
-        )}
-        {data.stats.edits > 0 && (
-          <span className="text-yellow-400">{data.stats.edits} edits</span>
-        )}
-        {data.stats.bash > 0 && (
-          <span className="text-orange-400">{data.stats.bash} bash</span>
+        {data.stats.errors > 0 && (
+          <span className="text-red-400">
+            {data.stats.errors} errors (
+            {Math.round(data.stats.errorRate * 100)}%)
+          </span>
         )}
-        {data.stats.other > 0 && (
-          <span className="text-muted-fg">{data.stats.other} other</span>
+        {data.stats.p95Ms != null && (
+          <span className="text-muted-fg">
+            p95 {formatDuration(data.stats.p95Ms)}
+          </span>
         )}
+        {Object.entries(data.stats.byTool)
+          .sort(([, a], [, b]) => b.count - a.count)
+          .map(([tool, breakdown]) => (
+            <span
+              key={tool}
+              className={breakdown.errors > 0 ? "text-red-400" : "text-fg"}
+              title={
+                breakdown.p50Ms != null
+                  ? `p50 ${formatDuration(breakdown.p50Ms)} / p95 ${formatDuration(breakdown.p95Ms ?? 0)}`
+                  : undefined
+              }
+            >
+              {breakdown.count} {tool}
+              {breakdown.errors > 0 && ` (${breakdown.errors} err)`}
+            </span>
+          ))}
       </div>
 
       <div className="max-h-80 overflow-auto space-y-1">
-        {data.toolCalls.map((call: ToolCall, i: number) => (
+        {data.toolCalls.map((call: ToolCall) => (
           <div
-            key={`${call.timestamp}-${i}`}
+            key={call.id}
             className="flex items-center gap-2 rounded px-2 py-1 text-xs hover:bg-secondary/50"
           >
             <span className="shrink-0 text-muted-fg font-mono w-16">
@@ -337,7 +349,7 @@ function TeamCard({ team }: { team: TeamSummary }) {
 
       <div>
         {team.members.map((m) => (
-          <MemberRow key={m.name} member={m} teamName={team.name} />
+          <MemberRow key={m.name} member={m} teamName={team.archiveId ?? team.name} />
         ))}
       </div>
 
@@ -433,7 +445,7 @@ export default function TeamsPage() {
 
       <div className="flex-1 overflow-auto p-4 space-y-4">
         {teams.map((team) => (
-          <TeamCard key={team.name} team={team} />
+          <TeamCard key={team.archiveId ?? team.name} team={team} />
         ))}
       </div>
     </div>
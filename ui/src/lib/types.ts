@@ -8,6 +8,7 @@ export interface Node {
   arch: string;
   daemonVersion: string;
   machineName?: string;
+  tlsFingerprint?: string;
 }
 
 // NOTE(victor): Must stay in sync with daemon/src/teams.rs API response types
@@ -34,6 +35,23 @@ export interface MemberSummary {
   isActive: boolean;
   color: string | null;
   diffSummary: DiffSummary | null;
+  usage: TokenUsage | null;
+  sessionLive: boolean;
+  sessionSource: string | null;
+}
+
+export interface TokenUsage {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheWriteTokens: number;
+  costUsd: number;
+}
+
+export interface TeamUsage {
+  team: string;
+  total: TokenUsage;
+  members: Record<string, TokenUsage>;
 }
 
 export interface TaskSummary {
@@ -53,6 +71,8 @@ export interface TeamSummary {
   description: string;
   createdAt: number;
   archived: boolean;
+  archivedAt: number | null;
+  archiveId: string | null;
   members: MemberSummary[];
   tasks: TaskSummary[];
   conflicts: ConflictInfo[];
@@ -68,6 +88,7 @@ export interface MemberDiffDetail {
 }
 
 export interface ToolCall {
+  id: string;
   tool: string;
   title: string | null;
   inputSummary: string;
@@ -76,13 +97,35 @@ export interface ToolCall {
   durationMs: number | null;
 }
 
+export interface ToolCallDetail {
+  id: string;
+  tool: string;
+  title: string | null;
+  input: unknown;
+  output: string | null;
+  outputTruncated: boolean;
+  error: string | null;
+  status: string;
+  timestamp: number;
+  durationMs: number | null;
+}
+
+export interface ToolBreakdown {
+  count: number;
+  errors: number;
+  p50Ms: number | null;
+  p95Ms: number | null;
+  maxMs: number | null;
+}
+
 export interface ToolStats {
   total: number;
-  reads: number;
-  writes: number;
-  edits: number;
-  bash: number;
-  other: number;
+  errors: number;
+  errorRate: number;
+  p50Ms: number | null;
+  p95Ms: number | null;
+  maxMs: number | null;
+  byTool: Record<string, ToolBreakdown>;
 }
 
 export interface MemberToolHistory {
@@ -92,3 +135,134 @@ export interface MemberToolHistory {
   toolCalls: ToolCall[];
   stats: ToolStats;
 }
+
+export interface TaskTransition {
+  status: string;
+  owner: string | null;
+  at: number;
+}
+
+export interface TaskTimes {
+  leadTimeMs: number | null;
+  cycleTimeMs: number | null;
+}
+
+export interface TaskHistory extends TaskTimes {
+  team: string;
+  id: string;
+  subject: string;
+  transitions: TaskTransition[];
+}
+
+export interface TaskMetrics extends TaskTimes {
+  id: string;
+  subject: string;
+  owner: string | null;
+}
+
+export interface MemberTaskMetrics {
+  completed: number;
+  avgLeadTimeMs: number | null;
+  avgCycleTimeMs: number | null;
+}
+
+export interface TeamTaskMetrics {
+  team: string;
+  tasks: TaskMetrics[];
+  members: Record<string, MemberTaskMetrics>;
+}
+
+export interface SearchHit {
+  team: string;
+  member: string;
+  tool: string | null;
+  kind: "tool_call" | "transcript";
+  refId: string;
+  timestamp: number;
+  snippet: string;
+}
+
+export interface WatcherStats {
+  incrementalRescans: number;
+  fullRescans: number;
+  fullRescansWithDrift: number;
+  driftedTeams: number;
+  lastDriftAt: number | null;
+}
+
+export interface CaptureSummary {
+  id: number;
+  at: number;
+  method: string;
+  path: string;
+  status: number | null;
+  durationMs: number;
+  upgrade: boolean;
+}
+
+export interface CapturedMessage {
+  headers: Record<string, string>;
+  body: string | null;
+  bodyBase64: string | null;
+  size: number;
+  truncated: boolean;
+}
+
+export interface Capture extends CaptureSummary {
+  request: CapturedMessage;
+  response: CapturedMessage;
+}
+
+export type WatcherState = "waiting" | "running" | "stopped";
+
+export interface OpencodeStatus {
+  answering: boolean;
+  status: number | null;
+  latencyMs: number | null;
+  error: string | null;
+}
+
+export interface UpdateStatus {
+  enabled: boolean;
+  lastCheckAt: number | null;
+  lastError: string | null;
+}
+
+export interface Readiness {
+  ready: boolean;
+  version: string;
+  uptimeSecs: number;
+  watchdogGeneration: number;
+  opencode: OpencodeStatus;
+  teamsWatcher: WatcherState;
+  lastHeartbeatAt: number | null;
+  update: UpdateStatus;
+}
+
+export type TeamEvent = {
+  type: "tool_call";
+  team: string;
+  member: string;
+  call: ToolCall;
+};
+
+export type TranscriptBlock =
+  | { type: "text"; text: string }
+  | { type: "reasoning"; text: string }
+  | { type: "tool_use"; id: string; tool: string }
+  | { type: "tool_result"; toolUseId: string; isError: boolean };
+
+export interface TranscriptMessage {
+  id: string;
+  role: string;
+  timestamp: number;
+  blocks: TranscriptBlock[];
+}
+
+export interface MemberTranscript {
+  name: string;
+  team: string;
+  backend: string;
+  messages: TranscriptMessage[];
+  nextCursor: string | null;
+}